@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    build_tower, configured_tower, exit_with_solve_error, finish_solve, json::Json,
+    report_checkpoint, save_manifest, write_checkpoint, Clock, Config, SolveError, SummaryFormat,
+};
+
+/// The outcome of solving one tower in a batch.
+struct Run {
+    height: u32,
+    moves: u64,
+    time: Duration,
+}
+
+impl Run {
+    pub fn moves_per_second(&self) -> f64 {
+        self.moves as f64 / self.time.as_secs_f64()
+    }
+}
+
+/// Solves a tower of every height in `config.heights`, one after another,
+/// then prints a report covering all of them.
+pub fn run(config: &Config) {
+    if config.heights.is_empty() {
+        println!("Please specify which heights to solve with --heights!");
+        println!("Do -H or --help for more information.");
+        std::process::exit(0);
+    }
+    let mut runs = Vec::new();
+    let mut last_tower = None;
+    for height in config.heights.iter().copied() {
+        let mut tower = if config.animate {
+            build_tower(config, height)
+        } else {
+            let mut tower = configured_tower(config, height, Clock::new(true));
+            tower.set_headless();
+            tower
+        };
+        let started = Instant::now();
+        match tower.solve() {
+            Ok(()) => finish_solve(config),
+            Err(SolveError::Unsolvable(e)) => {
+                save_manifest(config, &tower);
+                report(config, &runs, false);
+                eprintln!("Could not solve the tower of height {}: {}!", height, e);
+                std::process::exit(1);
+            }
+            Err(SolveError::Cancelled) => {
+                let saved = write_checkpoint(config, &tower);
+                save_manifest(config, &tower);
+                report(config, &runs, false);
+                eprintln!("Stopped during the tower of height {}", height);
+                report_checkpoint(config, saved);
+                std::process::exit(130);
+            }
+            Err(error) => exit_with_solve_error(config, &tower, error),
+        }
+        runs.push(Run {
+            height,
+            moves: tower.moves,
+            time: started.elapsed(),
+        });
+        last_tower = Some(tower);
+    }
+    report(config, &runs, true);
+    if let Some(tower) = &last_tower {
+        save_manifest(config, tower);
+    }
+}
+
+/// Prints the runs made so far, which with `completed` unset are not every
+/// height that was asked for.
+fn report(config: &Config, runs: &[Run], completed: bool) {
+    match config.summary_format {
+        SummaryFormat::Text => print_table(config, runs),
+        SummaryFormat::Json => println!("{}", to_json(runs, completed)),
+    }
+}
+
+fn print_table(config: &Config, runs: &[Run]) {
+    let locale = &config.locale;
+    let rows: Vec<[String; 4]> = runs
+        .iter()
+        .map(|run| {
+            [
+                run.height.to_string(),
+                locale.format_number(run.moves),
+                locale.format_duration(run.time),
+                locale.format_number(run.moves_per_second().round() as u64),
+            ]
+        })
+        .collect();
+    let headers = ["Height", "Moves", "Time", "Moves/s"];
+    let mut widths = [0; 4];
+    for row in rows
+        .iter()
+        .chain(std::iter::once(&headers.map(String::from)))
+    {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |row: &[String; 4]| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+    println!("{}", format_row(&headers.map(String::from)));
+    for row in rows.iter() {
+        println!("{}", format_row(row));
+    }
+}
+
+fn to_json(runs: &[Run], completed: bool) -> Json {
+    Json::Object(vec![
+        ("completed", Json::Bool(completed)),
+        (
+            "runs",
+            Json::Array(
+                runs.iter()
+                    .map(|run| {
+                        Json::Object(vec![
+                            ("height", Json::Integer(run.height as u64)),
+                            ("moves", Json::Integer(run.moves)),
+                            ("seconds", Json::Float(run.time.as_secs_f64())),
+                            ("moves_per_second", Json::Float(run.moves_per_second())),
+                        ])
+                    })
+                    .collect(),
+            ),
+        ),
+    ])
+}
@@ -0,0 +1,89 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{Clear, ClearType::All},
+};
+
+use crate::{to_io_error, Clock, Config, LogLevel, Tower, Tween};
+
+/// How many frames are drawn to measure the terminal.
+const CALIBRATION_FRAMES: u32 = 12;
+/// The most in-between frames calibration will add to a move.
+const MAX_TWEEN_FRAMES: u32 = 10;
+/// How many times the measured frame time each frame is given, so that
+/// a slower moment does not make the animation fall behind.
+const HEADROOM: u32 = 2;
+
+/// Draws the starting position a few times to see how fast this terminal is,
+/// then picks settings the user has not chosen to suit it: as many in-between
+/// frames as fit in the delay, and skipping frames when they do not all fit.
+pub fn run(config: &mut Config) {
+    let frame_time = match measure(config) {
+        Ok(frame_time) => frame_time,
+        Err(e) => {
+            eprintln!("Warning: could not calibrate the terminal: {}", e);
+            return;
+        }
+    };
+    let delay = Duration::from_millis(config.delay as u64);
+    let needed = (frame_time * HEADROOM).max(Duration::from_millis(1));
+    if config.tween.frames() == 0 {
+        let frames = (delay.as_nanos() / needed.as_nanos()) as u32;
+        config.tween = spread(frames.saturating_sub(1).min(MAX_TWEEN_FRAMES));
+    }
+    let frames = config.tween.frames().saturating_add(1);
+    if delay > Duration::from_millis(0) && needed * frames > delay {
+        config.skip_stalled = true;
+        config.adaptive = true;
+    }
+    if let LogLevel::All = config.log {
+        let tween = config.tween;
+        println!(
+            "Calibrated: about {} frames per second, tween {},{},{}, frame skipping {}",
+            config
+                .locale
+                .format_number((1.0 / frame_time.as_secs_f64().max(1e-6)).round() as u64),
+            tween.lift,
+            tween.slide,
+            tween.drop,
+            if config.skip_stalled { "on" } else { "off" }
+        );
+    }
+}
+
+/// The average time to clear the terminal and draw one frame.
+fn measure(config: &Config) -> std::io::Result<Duration> {
+    let tower = Tower::new(
+        config.height,
+        config.delay,
+        config.start,
+        config.goal,
+        Clock::new(true),
+    );
+    let frame = tower.to_string();
+    let mut stdout = std::io::stdout();
+    let started = Instant::now();
+    for _ in 0..CALIBRATION_FRAMES {
+        execute!(stdout, Clear(All), MoveTo(0, 0)).map_err(to_io_error)?;
+        writeln!(stdout, "{}", frame)?;
+        stdout.flush()?;
+    }
+    Ok(started.elapsed() / CALIBRATION_FRAMES)
+}
+
+/// Shares `frames` in-between frames out with a little more time for the
+/// slide than for the lift and drop.
+fn spread(frames: u32) -> Tween {
+    let lift = frames * 3 / 10;
+    let drop = frames * 3 / 10;
+    Tween {
+        lift,
+        slide: frames - lift - drop,
+        drop,
+    }
+}
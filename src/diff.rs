@@ -0,0 +1,96 @@
+use crossterm::style::{style, Attribute};
+
+use crate::{solution_moves, solver::optimal_moves, Clock, Peg, Tower, PEG_COUNT};
+
+const SEPARATOR: &str = " │ ";
+
+/// The two positions a diff compares.
+pub enum DiffInput {
+    Moves(usize, usize),
+    States(String, String),
+}
+
+impl DiffInput {
+    pub fn towers(&self, height: u32, start: Peg, goal: Peg) -> Result<(Tower, Tower), String> {
+        match self {
+            DiffInput::Moves(left, right) => {
+                let total = optimal_moves(height);
+                for index in [*left, *right].iter() {
+                    if *index as u64 > total {
+                        return Err(format!(
+                            "move {} is past the end of the {} move solution",
+                            index, total
+                        ));
+                    }
+                }
+                let moves = solution_moves(height, start, goal, *left.max(right));
+                let mut towers = Vec::new();
+                for index in [*left, *right].iter() {
+                    let mut tower = Tower::new(height, 0, start, goal, Clock::new(true));
+                    tower.apply_moves(&moves[..*index]);
+                    towers.push(tower);
+                }
+                let right = towers.pop().unwrap();
+                Ok((towers.pop().unwrap(), right))
+            }
+            DiffInput::States(left, right) => {
+                let left = Tower::parse_state(left).map_err(|e| format!("{}: {}", left, e))?;
+                let right = Tower::parse_state(right).map_err(|e| format!("{}: {}", right, e))?;
+                let mut left_tower = Tower::new(0, 0, start, goal, Clock::new(true));
+                let mut right_tower = Tower::new(0, 0, start, goal, Clock::new(true));
+                left_tower.set_state(left);
+                right_tower.set_state(right);
+                if left_tower.height != right_tower.height {
+                    return Err("both states must have the same number of disks".to_string());
+                }
+                Ok((left_tower, right_tower))
+            }
+        }
+    }
+
+    pub fn titles(&self) -> (String, String) {
+        match self {
+            DiffInput::Moves(left, right) => (
+                format!("After {} moves", left),
+                format!("After {} moves", right),
+            ),
+            DiffInput::States(left, right) => (left.to_owned(), right.to_owned()),
+        }
+    }
+}
+
+/// Renders two towers of the same height side by side, highlighting every
+/// cell whose disk differs between them.
+pub fn render_diff(left: &Tower, right: &Tower, titles: (String, String)) -> String {
+    let width = left.get_box_width() * PEG_COUNT;
+    let mut diff_string = format!(
+        "{:^width$}{}{:^width$}\n",
+        titles.0,
+        SEPARATOR,
+        titles.1,
+        width = width
+    );
+    for layer in (0..left.height as usize).rev() {
+        let mut row = [String::new(), String::new()];
+        for peg in Peg::all(PEG_COUNT) {
+            let differs = left.state[peg.index()].get(layer) != right.state[peg.index()].get(layer);
+            for (side, tower) in [left, right].iter().enumerate() {
+                let cell = tower.get_cell_string(peg, layer);
+                if differs {
+                    row[side].push_str(&style(cell).attribute(Attribute::Reverse).to_string());
+                } else {
+                    row[side].push_str(&cell);
+                }
+            }
+        }
+        diff_string.push_str(&format!("{}{}{}\n", row[0], SEPARATOR, row[1]));
+    }
+    let labels = left.get_label_string();
+    diff_string.push_str(&format!(
+        "{}{}{}",
+        labels.trim_end_matches('\n'),
+        SEPARATOR,
+        labels
+    ));
+    diff_string
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const ANSI_CLEAR: &str = "\x1b[2J\x1b[H";
+
+#[derive(Clone, Copy)]
+pub enum FrameFormat {
+    Txt,
+    Ansi,
+}
+
+impl FrameFormat {
+    /// The name --format takes.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameFormat::Txt => "txt",
+            FrameFormat::Ansi => "ansi",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FrameFormat::Txt => "txt",
+            FrameFormat::Ansi => "ans",
+        }
+    }
+}
+
+impl FromStr for FrameFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "txt" => Ok(FrameFormat::Txt),
+            "ansi" => Ok(FrameFormat::Ansi),
+            _ => Err(format!("{} is not a valid frame format", s)),
+        }
+    }
+}
+
+/// Writes each frame it is given into its own numbered file in a directory.
+pub struct FrameExport {
+    dir: PathBuf,
+    format: FrameFormat,
+    digits: usize,
+    next_index: usize,
+}
+
+impl FrameExport {
+    pub fn create(dir: &Path, format: FrameFormat, total_frames: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(FrameExport {
+            dir: dir.to_path_buf(),
+            format,
+            digits: total_frames.saturating_sub(1).to_string().len(),
+            next_index: 0,
+        })
+    }
+
+    /// Writes the recording's settings next to the frames as `metadata.txt`.
+    pub fn write_metadata(&self, header: &str) -> io::Result<()> {
+        fs::write(self.dir.join("metadata.txt"), header)
+    }
+
+    pub fn write(&mut self, frame: &str) -> io::Result<()> {
+        let file_name = format!(
+            "frame_{:0width$}.{}",
+            self.next_index,
+            self.format.extension(),
+            width = self.digits
+        );
+        let contents = match self.format {
+            FrameFormat::Txt => frame.to_string(),
+            FrameFormat::Ansi => format!("{}{}", ANSI_CLEAR, frame),
+        };
+        fs::write(self.dir.join(file_name), contents)?;
+        self.next_index += 1;
+        Ok(())
+    }
+
+    /// Numbers the next frame `index`, carrying on an export that stopped
+    /// after `index` frames were written.
+    pub fn resume_at(&mut self, index: usize) {
+        self.next_index = index;
+    }
+
+    pub fn format(&self) -> FrameFormat {
+        self.format
+    }
+
+    pub fn frames_written(&self) -> usize {
+        self.next_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_frame_numbers_to_the_last_frame() {
+        let dir = std::env::temp_dir().join(format!("hanoi-frames-{}", std::process::id()));
+        let mut export = FrameExport::create(&dir, FrameFormat::Ansi, 11).unwrap();
+        export.write("first").unwrap();
+        export.write("second").unwrap();
+        assert_eq!(export.frames_written(), 2);
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["frame_00.ans", "frame_01.ans"]);
+        let first = fs::read_to_string(dir.join("frame_00.ans")).unwrap();
+        assert_eq!(first, format!("{}first", ANSI_CLEAR));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn names_formats_as_parsed() {
+        for format in [FrameFormat::Txt, FrameFormat::Ansi].iter() {
+            let parsed: FrameFormat = format.name().parse().unwrap();
+            assert_eq!(parsed.extension(), format.extension());
+        }
+        assert!("png".parse::<FrameFormat>().is_err());
+    }
+}
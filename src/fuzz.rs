@@ -0,0 +1,107 @@
+use std::io::Read;
+
+use crate::{
+    solver::{self, Frozen, Rules},
+    Clock, Peg, Tower, PEG_COUNT,
+};
+
+/// The tallest tower a fuzz input can build, kept small so searches stay fast.
+const MAX_FUZZ_HEIGHT: u32 = 6;
+
+/// Reads a whole fuzz input from stdin and runs it through [`run`].
+pub fn run_stdin() {
+    let mut data = Vec::new();
+    std::io::stdin().read_to_end(&mut data).unwrap();
+    run(&data);
+}
+
+/// Builds a scrambled tower and a sequence of moves out of arbitrary bytes,
+/// panicking if the tower ever breaks one of its invariants.
+///
+/// The first byte picks the height, the next picks the goal peg and the
+/// rules, then one byte per disk picks its peg. Every byte after that is a
+/// move, which must be rejected without changing anything when illegal.
+/// Disks that would overfill a peg are placed on the next one instead.
+pub fn run(data: &[u8]) {
+    let mut bytes = data.iter().copied();
+    let height = bytes
+        .next()
+        .map_or(1, |byte| byte as u32 % MAX_FUZZ_HEIGHT + 1);
+    let setup = bytes.next().unwrap_or(0);
+    let goal = Peg::new(setup as usize % PEG_COUNT);
+    let mut rules = Rules::classic();
+    if setup & 0b0100 != 0 {
+        rules.capacities[(setup as usize >> 4) % PEG_COUNT] = Some(height - 1);
+    }
+    if setup & 0b1000 != 0 {
+        rules.frozen = Some(Frozen {
+            disk: (setup as u32 >> 6) % height + 1,
+            moves: (setup as u64 >> 4) % 4,
+        });
+    }
+
+    let mut state = [Vec::new(), Vec::new(), Vec::new()];
+    for disk in (1..=height).rev() {
+        let mut peg = bytes.next().unwrap_or(0) as usize % PEG_COUNT;
+        while rules.capacities[peg].is_some_and(|capacity| state[peg].len() as u32 >= capacity) {
+            peg = (peg + 1) % PEG_COUNT;
+        }
+        state[peg].push(disk);
+    }
+    let start = Peg::new((goal.index() + 1) % PEG_COUNT);
+    let mut tower = Tower::new(0, 0, start, goal, Clock::new(true));
+    tower.set_state(state.clone());
+    if let Some(moves) = solver::search(&state, goal, &rules) {
+        check_solution(&tower, &rules, &moves);
+    }
+    tower.set_rules(rules);
+
+    let mut made = 0;
+    for byte in bytes {
+        let from = Peg::new(byte as usize % PEG_COUNT);
+        let to = Peg::new(byte as usize / PEG_COUNT % PEG_COUNT);
+        let before = tower.state.clone();
+        if tower.try_move(from, to) {
+            made += 1;
+        } else {
+            assert_eq!(before, tower.state, "a rejected move changed the tower");
+        }
+        check_invariants(&tower, made);
+    }
+}
+
+fn check_invariants(tower: &Tower, made: u64) {
+    if let Err(e) = Tower::check_state(&tower.state) {
+        panic!("invalid state {:?}: {}", tower.state, e);
+    }
+    assert_eq!(tower.moves, made, "the move counter drifted");
+    assert!(tower.placed_disks() <= tower.height);
+    for (peg, capacity) in tower.rules.capacities.iter().enumerate() {
+        if let Some(capacity) = capacity {
+            assert!(
+                tower.state[peg].len() as u32 <= *capacity,
+                "peg {} is over capacity",
+                peg
+            );
+        }
+    }
+}
+
+/// Replays a searched solution on a copy of the rules, checking every move is
+/// legal and that it ends with the whole tower on the goal peg.
+fn check_solution(tower: &Tower, rules: &Rules, moves: &[(Peg, Peg)]) {
+    let mut state = tower.state.clone();
+    for (moves_made, (from, to)) in moves.iter().enumerate() {
+        assert!(
+            rules.allows(&state, moves_made as u64, *from, *to),
+            "search made an illegal move"
+        );
+        let disk = state[from.index()].pop().unwrap();
+        state[to.index()].push(disk);
+    }
+    assert_eq!(
+        state[tower.goal.index()].len() as u32,
+        tower.height,
+        "search did not finish on the goal peg"
+    );
+}
@@ -0,0 +1,103 @@
+use crossterm::style::{style, Color};
+
+use crate::{json::Json, locale::Locale, Peg, PEG_COUNT};
+
+/// Counts how often each (peg, level) cell held a disk, sampled once at the
+/// start and once after every move.
+pub struct Heatmap {
+    counts: Vec<[u64; PEG_COUNT]>,
+    samples: u64,
+}
+
+impl Heatmap {
+    pub fn new(height: u32) -> Self {
+        Heatmap {
+            counts: vec![[0; PEG_COUNT]; height as usize],
+            samples: 0,
+        }
+    }
+
+    pub fn record(&mut self, state: &[Vec<u32>; PEG_COUNT]) {
+        for (peg, disks) in state.iter().enumerate() {
+            for level in 0..disks.len() {
+                self.counts[level][peg] += 1;
+            }
+        }
+        self.samples += 1;
+    }
+
+    fn share(&self, peg: Peg, level: usize) -> f64 {
+        self.counts[level][peg.index()] as f64 / self.samples.max(1) as f64
+    }
+
+    /// Draws one box per cell in the board's layout, shaded from dark blue
+    /// for never occupied to red for always occupied, with its share written in.
+    pub fn render(&self, box_width: usize, locale: &Locale) -> String {
+        let mut heatmap_string = String::new();
+        for level in (0..self.counts.len()).rev() {
+            for peg in Peg::all(PEG_COUNT) {
+                let share = self.share(peg, level);
+                let cell = format!(
+                    "{:^width$}",
+                    locale.format_percent(share),
+                    width = box_width
+                );
+                heatmap_string
+                    .push_str(&style(cell).with(Color::White).on(heat(share)).to_string());
+            }
+            heatmap_string.push('\n');
+        }
+        for peg in Peg::all(PEG_COUNT) {
+            heatmap_string.push_str(&format!("{:^width$}", peg.name(), width = box_width));
+        }
+        heatmap_string
+    }
+
+    /// The share of samples each cell was occupied, one array of pegs per
+    /// level from the bottom up.
+    pub fn to_json(&self) -> Json {
+        Json::Array(
+            (0..self.counts.len())
+                .map(|level| {
+                    Json::Array(
+                        Peg::all(PEG_COUNT)
+                            .map(|peg| Json::Float(self.share(peg, level)))
+                            .collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Blends from dark blue through purple to red as `share` goes from 0 to 1.
+fn heat(share: f64) -> Color {
+    let channel = |from: f64, to: f64| (from + (to - from) * share).round() as u8;
+    Color::Rgb {
+        r: channel(0.0, 200.0),
+        g: channel(0.0, 30.0),
+        b: channel(90.0, 30.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_count_every_sample() {
+        let mut heatmap = Heatmap::new(2);
+        heatmap.record(&[vec![2, 1], vec![], vec![]]);
+        heatmap.record(&[vec![2], vec![1], vec![]]);
+        assert_eq!(heatmap.share(Peg::new(0), 0), 1.0);
+        assert_eq!(heatmap.share(Peg::new(0), 1), 0.5);
+        assert_eq!(heatmap.share(Peg::new(1), 0), 0.5);
+        assert_eq!(heatmap.share(Peg::new(2), 0), 0.0);
+        assert_eq!(heatmap.to_json().to_string(), "[[1,0.5,0],[0.5,0,0]]");
+    }
+
+    #[test]
+    fn empty_heatmap_has_no_shares() {
+        assert_eq!(Heatmap::new(1).share(Peg::new(0), 0), 0.0);
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// A JSON value, just enough to write reports without pulling in a serializer.
+pub enum Json {
+    Bool(bool),
+    Integer(u64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Integer(value) => write!(f, "{}", value),
+            Json::Float(value) if value.is_finite() => write!(f, "{}", value),
+            Json::Float(_) => write!(f, "null"),
+            Json::String(value) => {
+                write!(f, "\"")?;
+                for c in value.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        '\n' => write!(f, "\\n")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", Json::String(key.to_string()), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_strings() {
+        let string = Json::String("say \"hi\"\\\n\t→".to_string());
+        assert_eq!(string.to_string(), r#""say \"hi\"\\\n\u0009→""#);
+    }
+
+    #[test]
+    fn writes_nested_values() {
+        let value = Json::Object(vec![
+            ("done", Json::Bool(true)),
+            (
+                "moves",
+                Json::Array(vec![Json::Integer(1), Json::Float(0.5)]),
+            ),
+            ("ratio", Json::Float(f64::NAN)),
+            ("empty", Json::Object(Vec::new())),
+        ]);
+        assert_eq!(
+            value.to_string(),
+            r#"{"done":true,"moves":[1,0.5],"ratio":null,"empty":{}}"#
+        );
+    }
+}
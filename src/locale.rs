@@ -0,0 +1,167 @@
+use std::{env, time::Duration};
+
+/// Number and duration formatting conventions for a language.
+#[derive(Clone, Copy)]
+pub struct Locale {
+    language: &'static str,
+    group_separator: &'static str,
+    decimal_separator: char,
+}
+
+/// The separators of every supported language, for numbers and ratios.
+const LANGUAGES: [(&str, &str, char); 18] = [
+    ("en", ",", '.'),
+    ("c", ",", '.'),
+    ("posix", ",", '.'),
+    ("de", ".", ','),
+    ("es", ".", ','),
+    ("it", ".", ','),
+    ("nl", ".", ','),
+    ("pt", ".", ','),
+    ("da", ".", ','),
+    ("id", ".", ','),
+    ("fr", "\u{202f}", ','),
+    ("sv", "\u{202f}", ','),
+    ("fi", "\u{202f}", ','),
+    ("nb", "\u{202f}", ','),
+    ("pl", "\u{202f}", ','),
+    ("cs", "\u{202f}", ','),
+    ("ru", "\u{202f}", ','),
+    ("uk", "\u{202f}", ','),
+];
+
+impl Locale {
+    /// Looks up a locale by tag, e.g. `de`, `de_DE` or `fr_FR.UTF-8`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        LANGUAGES.iter().find(|(name, _, _)| *name == language).map(
+            |(language, group_separator, decimal_separator)| Locale {
+                language,
+                group_separator,
+                decimal_separator: *decimal_separator,
+            },
+        )
+    }
+
+    /// The language the locale was looked up by, e.g. `de` for `de_DE.UTF-8`.
+    pub fn tag(&self) -> &'static str {
+        self.language
+    }
+
+    /// Picks the locale from the usual environment variables, falling back to English.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::from_tag(&value))
+            .unwrap_or_else(|| Locale::from_tag("en").unwrap())
+    }
+
+    pub fn format_number(&self, number: u64) -> String {
+        let digits = number.to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push_str(self.group_separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// Formats a ratio as a percentage with one decimal place.
+    pub fn format_percent(&self, ratio: f64) -> String {
+        let tenths = (ratio * 1000.0).round() as u64;
+        format!(
+            "{}{}{}%",
+            self.format_number(tenths / 10),
+            self.decimal_separator,
+            tenths % 10
+        )
+    }
+
+    /// Formats a duration as milliseconds below a second, otherwise as seconds
+    /// with one decimal place, adding larger units once it reaches a minute.
+    pub fn format_duration(&self, duration: Duration) -> String {
+        if duration < Duration::from_secs(1) {
+            return format!("{} ms", duration.as_millis());
+        }
+        let tenths = duration.as_millis() / 100;
+        let seconds = format!(
+            "{}{}{} s",
+            tenths / 10 % 60,
+            self.decimal_separator,
+            tenths % 10
+        );
+        let minutes = tenths / 600;
+        match minutes {
+            0 => seconds,
+            1..=59 => format!("{} min {}", minutes, seconds),
+            _ => format!(
+                "{} h {} min {}",
+                self.format_number((minutes / 60) as u64),
+                minutes % 60,
+                seconds
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_tags_by_language() {
+        assert_eq!(Locale::from_tag("de_DE.UTF-8").unwrap().tag(), "de");
+        assert_eq!(Locale::from_tag("en-GB").unwrap().tag(), "en");
+        assert_eq!(Locale::from_tag("C").unwrap().tag(), "c");
+        assert!(Locale::from_tag("xx").is_none());
+    }
+
+    #[test]
+    fn groups_digits() {
+        let en = Locale::from_tag("en").unwrap();
+        assert_eq!(en.format_number(0), "0");
+        assert_eq!(en.format_number(999), "999");
+        assert_eq!(en.format_number(1000), "1,000");
+        assert_eq!(en.format_number(1234567), "1,234,567");
+        assert_eq!(
+            Locale::from_tag("de").unwrap().format_number(1234567),
+            "1.234.567"
+        );
+        assert_eq!(
+            Locale::from_tag("fr").unwrap().format_number(1234),
+            "1\u{202f}234"
+        );
+    }
+
+    #[test]
+    fn formats_percentages() {
+        assert_eq!(
+            Locale::from_tag("en").unwrap().format_percent(0.125),
+            "12.5%"
+        );
+        assert_eq!(
+            Locale::from_tag("de").unwrap().format_percent(1.0),
+            "100,0%"
+        );
+    }
+
+    #[test]
+    fn formats_durations() {
+        let en = Locale::from_tag("en").unwrap();
+        assert_eq!(en.format_duration(Duration::from_millis(250)), "250 ms");
+        assert_eq!(en.format_duration(Duration::from_millis(1500)), "1.5 s");
+        assert_eq!(en.format_duration(Duration::from_secs(61)), "1 min 1.0 s");
+        assert_eq!(
+            en.format_duration(Duration::from_secs(3600 * 1001)),
+            "1,001 h 0 min 0.0 s"
+        );
+    }
+}
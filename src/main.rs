@@ -1,18 +1,66 @@
-use std::{env, fmt, process};
+use std::{
+    collections::VecDeque,
+    convert::TryFrom,
+    env, fmt,
+    fs::File,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+mod batch;
+mod calibrate;
+mod diff;
+mod frames;
+mod fuzz;
+mod heatmap;
+mod json;
+mod locale;
+mod peg;
+mod scenario;
+mod selftest;
+mod setup;
+mod solver;
+mod status;
+mod symmetry;
 
 use crossterm::{
     cursor::{DisableBlinking, Hide, MoveTo},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
-    terminal::{Clear, ClearType::All},
+    style::{style, Color},
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType::All},
 };
+use diff::DiffInput;
+use frames::{FrameExport, FrameFormat};
+use heatmap::Heatmap;
+use json::Json;
+use locale::Locale;
+use peg::Peg;
+use solver::{Frozen, Rules, MAX_SEARCH_HEIGHT, MAX_SEARCH_STATES};
+use status::{StatusField, StatusTemplate};
 
 const DELAY_MS: u64 = 100;
 const TOWER_SIZE: u32 = 6;
+const PEG_COUNT: usize = 3;
+const SPARKLINE_LENGTH: usize = 32;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const CHECKPOINT_FILE: &str = "hanoi-checkpoint.txt";
+const SESSION_FILE: &str = ".hanoi-session";
+const STALL_FACTOR: u32 = 10;
+const STALL_MIN_MS: u64 = 250;
+const ADAPT_MIN_MS: u64 = 16;
+const ADAPT_FRAMES: u32 = 8;
+const MAX_PHASE_FRAMES: u32 = 1000;
 
-enum Column {
-    First,
-    Second,
-    Third,
+enum Clock {
+    Real { start: Instant },
+    Simulated { elapsed: Duration },
 }
 
 enum LogLevel {
@@ -21,12 +69,222 @@ enum LogLevel {
     All,
 }
 
-impl Column {
-    pub fn get_value(&self) -> usize {
+impl LogLevel {
+    pub fn parse(string: &str) -> Option<Self> {
+        match &string.to_lowercase()[..] {
+            "all" => Some(LogLevel::All),
+            "minimal" => Some(LogLevel::Minimal),
+            "none" => Some(LogLevel::None),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogLevel::All => "all",
+            LogLevel::Minimal => "minimal",
+            LogLevel::None => "none",
+        }
+    }
+}
+
+/// Whether moves follow each other on their own or one per keypress.
+#[derive(Clone, Copy, PartialEq)]
+enum StepMode {
+    Auto,
+    Manual,
+}
+
+impl StepMode {
+    pub fn parse(string: &str) -> Option<Self> {
+        match &string.to_lowercase()[..] {
+            "auto" => Some(StepMode::Auto),
+            "manual" => Some(StepMode::Manual),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StepMode::Auto => "auto",
+            StepMode::Manual => "manual",
+        }
+    }
+}
+
+impl Clock {
+    pub fn new(fast_forward: bool) -> Self {
+        if fast_forward {
+            Clock::Simulated {
+                elapsed: Duration::from_millis(0),
+            }
+        } else {
+            Clock::Real {
+                start: Instant::now(),
+            }
+        }
+    }
+
+    pub fn sleep(&mut self, duration: Duration) {
+        match self {
+            Clock::Real { .. } => std::thread::sleep(duration),
+            Clock::Simulated { elapsed } => *elapsed += duration,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self {
+            Clock::Real { start } => start.elapsed(),
+            Clock::Simulated { elapsed } => *elapsed,
+        }
+    }
+}
+
+enum Command {
+    Solve,
+    Frames,
+    Diff,
+    Scenario(String),
+    Fuzz,
+    Batch,
+    Selftest,
+    Symmetry,
+    Setup,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Solve => write!(f, "solve"),
+            Command::Frames => write!(f, "frames"),
+            Command::Diff => write!(f, "diff"),
+            Command::Scenario(path) => write!(f, "scenario {}", path),
+            Command::Fuzz => write!(f, "fuzz"),
+            Command::Batch => write!(f, "batch"),
+            Command::Selftest => write!(f, "selftest"),
+            Command::Symmetry => write!(f, "symmetry"),
+            Command::Setup => write!(f, "setup"),
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum SummaryFormat {
+    Text,
+    Json,
+}
+
+impl SummaryFormat {
+    pub fn parse(string: &str) -> Option<Self> {
+        match &string.to_lowercase()[..] {
+            "text" => Some(SummaryFormat::Text),
+            "json" => Some(SummaryFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
         match self {
-            Column::First => 0,
-            Column::Second => 1,
-            Column::Third => 2,
+            SummaryFormat::Text => "text",
+            SummaryFormat::Json => "json",
+        }
+    }
+}
+
+struct Config {
+    command: Command,
+    delay: u32,
+    height: u32,
+    log: LogLevel,
+    fast_forward: bool,
+    dump_frames: Option<String>,
+    show_status: bool,
+    status_format: StatusTemplate,
+    show_progress: bool,
+    sparkline: Option<usize>,
+    skip_stalled: bool,
+    adaptive: bool,
+    step_mode: StepMode,
+    calibrate: bool,
+    heatmap: bool,
+    start: Peg,
+    goal: Peg,
+    frames_out: Option<String>,
+    frames_format: FrameFormat,
+    diff_input: Option<DiffInput>,
+    locale: Locale,
+    capacities: [Option<u32>; PEG_COUNT],
+    frozen: Option<Frozen>,
+    tween: Tween,
+    disk_colors: Vec<(u32, Color)>,
+    heights: Vec<u32>,
+    animate: bool,
+    summary_format: SummaryFormat,
+    checkpoint: String,
+    resume_from: u64,
+    resume_frames: usize,
+    resumed_checkpoint: Option<String>,
+    manifest: Option<String>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Config {
+    /// Checks the settings that depend on each other, such as a frozen disk
+    /// existing in a tower of the chosen height.
+    /// A batch checks every height it will solve.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.start == self.goal {
+            return Err("The start and goal pegs must be different".to_string());
+        }
+        if let (Some(_), Command::Diff | Command::Fuzz | Command::Selftest | Command::Symmetry) =
+            (&self.manifest, &self.command)
+        {
+            return Err(format!(
+                "The {} command does not write a manifest",
+                self.command
+            ));
+        }
+        let heights = match self.command {
+            Command::Batch if !self.heights.is_empty() => &self.heights[..],
+            _ => std::slice::from_ref(&self.height),
+        };
+        for height in heights.iter().copied() {
+            if let Some(frozen) = self.frozen {
+                if frozen.disk == 0 || frozen.disk > height {
+                    return Err(format!(
+                        "There is no disk {} in a tower of height {}",
+                        frozen.disk, height
+                    ));
+                }
+                // A freeze the classic solution outlasts has to be searched,
+                // one layer of states for every frozen move.
+                if frozen.moves > solver::optimal_moves(frozen.disk - 1)
+                    && frozen.moves > MAX_SEARCH_STATES as u64
+                {
+                    return Err(format!(
+                        "Disk {} cannot stay frozen for more than {} moves",
+                        frozen.disk, MAX_SEARCH_STATES
+                    ));
+                }
+            }
+            for peg in [self.start, self.goal].iter() {
+                if let Some(capacity) = self.capacities[peg.index()] {
+                    if capacity < height {
+                        return Err(format!(
+                            "Peg {} must be able to hold all {} disks",
+                            peg, height
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules {
+            capacities: self.capacities,
+            frozen: self.frozen,
         }
     }
 }
@@ -34,224 +292,2147 @@ impl Column {
 struct Tower {
     height: u32,
     print_delay: u32,
-    state: [Vec<u32>; 3],
+    start: Peg,
+    goal: Peg,
+    state: [Vec<u32>; PEG_COUNT],
+    clock: Clock,
+    frame_dump: Option<File>,
+    frame_export: Option<FrameExport>,
+    headless: bool,
+    step_mode: StepMode,
+    show_status: bool,
+    status_format: StatusTemplate,
+    locale: Locale,
+    show_progress: bool,
+    sparkline: Option<usize>,
+    recent_disks: VecDeque<u32>,
+    tween: Tween,
+    disk_colors: Vec<(u32, Color)>,
+    rules: Rules,
+    moves: u64,
+    total_moves: u64,
+    frames_drawn: u64,
+    exported_frames: usize,
+    stats: Stats,
+    watchdog: Watchdog,
+    pauses: Vec<(u64, Duration)>,
+    paused: Duration,
+    resume_from: u64,
+    started: Instant,
+    sub_problems: Vec<SubProblem>,
+    history: Option<Vec<(Peg, Peg)>>,
+    heatmap: Option<Heatmap>,
+    cancel: Option<Arc<AtomicBool>>,
+    plan: Option<Plan>,
+}
+
+/// How a tower will be solved, worked out before the first move.
+enum Plan {
+    ClosedForm,
+    Search(Vec<(Peg, Peg)>),
+}
+
+/// How much of the animation reaches the terminal, from everything down to
+/// skipping frames whenever drawing falls behind.
+#[derive(Clone, Copy, PartialEq)]
+enum Fidelity {
+    Full,
+    Uncolored,
+    Untweened,
+    Skipping,
+}
+
+impl Fidelity {
+    pub fn lower(self) -> Self {
+        match self {
+            Fidelity::Full => Fidelity::Uncolored,
+            Fidelity::Uncolored => Fidelity::Untweened,
+            Fidelity::Untweened | Fidelity::Skipping => Fidelity::Skipping,
+        }
+    }
+
+    pub fn higher(self) -> Self {
+        match self {
+            Fidelity::Full | Fidelity::Uncolored => Fidelity::Full,
+            Fidelity::Untweened => Fidelity::Uncolored,
+            Fidelity::Skipping => Fidelity::Untweened,
+        }
+    }
+}
+
+/// Notices frames that take far longer to draw than the delay between them,
+/// and optionally skips drawing frames until the animation has caught up.
+struct Watchdog {
+    skip_stalled: bool,
+    stalls: u32,
+    skipped_frames: u32,
+    behind: Duration,
+    adaptive: bool,
+    fidelity: Fidelity,
+    latency: Duration,
+    frames_at_fidelity: u32,
+    downgrades: u32,
+}
+
+impl Watchdog {
+    pub fn new(skip_stalled: bool) -> Self {
+        Watchdog {
+            skip_stalled,
+            stalls: 0,
+            skipped_frames: 0,
+            behind: Duration::from_millis(0),
+            adaptive: false,
+            fidelity: Fidelity::Full,
+            latency: Duration::from_millis(0),
+            frames_at_fidelity: 0,
+            downgrades: 0,
+        }
+    }
+
+    /// Returns whether the next frame should be skipped to catch up.
+    pub fn should_skip(&mut self, delay: Duration) -> bool {
+        let skipping = self.skip_stalled || self.fidelity == Fidelity::Skipping;
+        if !skipping || self.behind == Duration::from_millis(0) {
+            return false;
+        }
+        self.behind = self.behind.checked_sub(delay).unwrap_or_default();
+        if delay == Duration::from_millis(0) {
+            self.behind = Duration::from_millis(0);
+        }
+        self.skipped_frames += 1;
+        true
+    }
+
+    /// Records how long a frame took to draw, returning whether it stalled.
+    pub fn check(&mut self, render_time: Duration, delay: Duration) -> bool {
+        let threshold = (delay * STALL_FACTOR).max(Duration::from_millis(STALL_MIN_MS));
+        if render_time <= threshold {
+            return false;
+        }
+        self.stalls += 1;
+        self.behind += render_time - delay.min(render_time);
+        true
+    }
+
+    /// Keeps a running average of how long frames take to draw, lowering the
+    /// fidelity a step while it is over `budget` and raising it again once it
+    /// is comfortably under. Each step is held for a few frames before the next.
+    pub fn adapt(&mut self, render_time: Duration, budget: Duration) {
+        if !self.adaptive {
+            return;
+        }
+        let budget = budget.max(Duration::from_millis(ADAPT_MIN_MS));
+        self.latency = self.latency * 7 / 8 + render_time / 8;
+        if self.fidelity == Fidelity::Skipping {
+            self.behind += render_time.checked_sub(budget).unwrap_or_default();
+        }
+        self.frames_at_fidelity += 1;
+        if self.frames_at_fidelity < ADAPT_FRAMES {
+            return;
+        }
+        let fidelity = if self.latency > budget {
+            self.fidelity.lower()
+        } else if self.latency < budget / 2 {
+            self.fidelity.higher()
+        } else {
+            self.fidelity
+        };
+        if fidelity != self.fidelity {
+            if self.latency > budget {
+                self.downgrades += 1;
+            }
+            self.fidelity = fidelity;
+            self.frames_at_fidelity = 0;
+        }
+    }
+}
+
+/// A stack of `size` disks being relocated as one step of the solution.
+struct SubProblem {
+    size: u32,
+    from: Peg,
+    to: Peg,
+    via: Peg,
 }
 
 impl fmt::Display for Tower {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let max_height = self.height as usize;
-        let mut printed_str = String::new();
-        for i in (0..max_height).rev() {
-            printed_str.push_str(&self.get_layer_string(i).to_owned());
+        write!(f, "{}", self.get_board_string(None, false))
+    }
+}
+
+/// How often each peg and disk took part in a move.
+struct Stats {
+    from_peg: [u64; PEG_COUNT],
+    to_peg: [u64; PEG_COUNT],
+    disk_moves: Vec<u64>,
+}
+
+impl Stats {
+    pub fn new(height: u32) -> Self {
+        Stats {
+            from_peg: [0; PEG_COUNT],
+            to_peg: [0; PEG_COUNT],
+            disk_moves: vec![0; height as usize],
+        }
+    }
+
+    pub fn record(&mut self, disk: u32, from: Peg, to: Peg) {
+        self.from_peg[from.index()] += 1;
+        self.to_peg[to.index()] += 1;
+        self.disk_moves[disk as usize - 1] += 1;
+    }
+}
+
+/// A disk in the air between pegs, `center` characters from the left edge.
+#[derive(PartialEq)]
+struct Floating {
+    disk: u32,
+    row: usize,
+    center: usize,
+}
+
+/// How many in-between frames to draw for each phase of a move.
+#[derive(Clone, Copy)]
+struct Tween {
+    lift: u32,
+    slide: u32,
+    drop: u32,
+}
+
+impl Tween {
+    pub fn none() -> Self {
+        Tween {
+            lift: 0,
+            slide: 0,
+            drop: 0,
         }
-        write!(f, "{}", printed_str)
     }
+
+    pub fn frames(&self) -> u32 {
+        self.lift
+            .saturating_add(self.slide)
+            .saturating_add(self.drop)
+    }
+}
+
+/// Slows down as it reaches the top, for lifting a disk off a peg.
+fn ease_out(t: f64) -> f64 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+/// Speeds up as it falls, for dropping a disk onto a peg.
+fn ease_in(t: f64) -> f64 {
+    t * t
+}
+
+fn ease_in_out(t: f64) -> f64 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+    }
+}
+
+fn lerp(from: usize, to: usize, t: f64) -> usize {
+    (from as f64 + (to as f64 - from as f64) * t).round() as usize
 }
 
 impl Tower {
-    pub fn new(height: u32, delay: u32) -> Self {
+    pub fn new(height: u32, delay: u32, start: Peg, goal: Peg, clock: Clock) -> Self {
         let mut starting_col = Vec::new();
         for i in 0..height {
             starting_col.push(height - i);
         }
-        let state = [starting_col, Vec::new(), Vec::new()];
+        let mut state = [Vec::new(), Vec::new(), Vec::new()];
+        state[start.index()] = starting_col;
         Tower {
             height,
             print_delay: delay,
+            start,
+            goal,
             state,
+            clock,
+            frame_dump: None,
+            frame_export: None,
+            headless: false,
+            step_mode: StepMode::Auto,
+            show_status: false,
+            status_format: StatusTemplate::action(),
+            locale: Locale::from_tag("en").unwrap(),
+            show_progress: false,
+            sparkline: None,
+            recent_disks: VecDeque::new(),
+            tween: Tween::none(),
+            disk_colors: Vec::new(),
+            rules: Rules::classic(),
+            moves: 0,
+            total_moves: 0,
+            frames_drawn: 0,
+            exported_frames: 0,
+            stats: Stats::new(height),
+            watchdog: Watchdog::new(false),
+            pauses: Vec::new(),
+            paused: Duration::from_millis(0),
+            resume_from: 0,
+            started: Instant::now(),
+            sub_problems: Vec::new(),
+            history: None,
+            heatmap: None,
+            cancel: None,
+            plan: None,
+        }
+    }
+
+    /// Parses a state such as `"3,2/1/"`: pegs are separated by `/` and
+    /// list their disks from the bottom up, separated by `,`.
+    pub fn parse_state(string: &str) -> Result<[Vec<u32>; PEG_COUNT], String> {
+        let pegs: Vec<&str> = string.split('/').collect();
+        if pegs.len() != PEG_COUNT {
+            return Err(format!("expected {} pegs separated by '/'", PEG_COUNT));
         }
+        let mut state = [Vec::new(), Vec::new(), Vec::new()];
+        for (peg, disks) in pegs.iter().enumerate() {
+            for disk in disks.split(',').filter(|disk| !disk.trim().is_empty()) {
+                match disk.trim().parse::<u32>() {
+                    Ok(disk) if disk > 0 => state[peg].push(disk),
+                    _ => return Err(format!("{} is not a valid disk", disk)),
+                }
+            }
+        }
+        Tower::check_state(&state)?;
+        Ok(state)
+    }
+
+    /// Checks that no disk sits on a smaller one and that the disks are
+    /// numbered 1 to the tower height, once each.
+    pub fn check_state(state: &[Vec<u32>; PEG_COUNT]) -> Result<(), String> {
+        for peg in Peg::all(PEG_COUNT) {
+            if state[peg.index()].windows(2).any(|pair| pair[0] < pair[1]) {
+                return Err(format!("peg {} has a larger disk on a smaller one", peg));
+            }
+        }
+        let mut disks: Vec<u32> = state.iter().flatten().copied().collect();
+        disks.sort_unstable();
+        if disks
+            .iter()
+            .zip(1..)
+            .any(|(disk, expected)| *disk != expected)
+        {
+            return Err("disks must be numbered 1 to the tower height, once each".to_string());
+        }
+        Ok(())
     }
 
-    pub fn solve(&mut self) {
-        self.move_stack(self.height, &Column::First, &Column::Third, &Column::Second);
+    /// Replaces the disks on the board; the tower height becomes the number of disks.
+    pub fn set_state(&mut self, state: [Vec<u32>; PEG_COUNT]) {
+        self.height = state.iter().map(|peg| peg.len() as u32).sum();
+        self.state = state;
+        self.stats = Stats::new(self.height);
     }
 
-    fn move_peg(&mut self, from: &Column, to: &Column) {
-        let from = from.get_value();
-        let to = to.get_value();
+    pub fn apply_moves(&mut self, moves: &[(Peg, Peg)]) {
+        for (from, to) in moves {
+            self.move_peg(*from, *to);
+        }
+    }
 
-        let val = self.state[from].pop().unwrap();
-        self.state[to].push(val);
+    pub fn show_status(&mut self, show: bool) {
+        self.show_status = show;
     }
 
-    fn move_stack(&mut self, size: u32, start_col: &Column, target_col: &Column, aux_col: &Column) {
-        if size > 0 {
-            self.move_stack(size - 1, start_col, aux_col, target_col);
-            self.move_and_print(start_col, target_col);
-            self.move_stack(size - 1, aux_col, target_col, start_col);
+    /// Lays out the status bar shown with [`Tower::show_status`].
+    pub fn set_status_format(&mut self, status_format: StatusTemplate) {
+        self.status_format = status_format;
+    }
+
+    /// Sets how numbers and durations in the status bar are written.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Shows which disk moved in each of the last `length` moves in the status bar.
+    pub fn show_sparkline(&mut self, length: Option<usize>) {
+        self.sparkline = length;
+    }
+
+    pub fn dump_frames_to(&mut self, file: File) {
+        self.frame_dump = Some(file);
+    }
+
+    /// Sends every frame to `export` instead of drawing it in the terminal,
+    /// starting with the board as it stands unless `export` is being resumed.
+    pub fn export_frames(&mut self, mut export: FrameExport) -> std::io::Result<()> {
+        if export.frames_written() == 0 {
+            let colored = matches!(export.format(), FrameFormat::Ansi);
+            export.write(&self.get_board_string(None, colored))?;
         }
+        self.exported_frames = export.frames_written();
+        self.frame_export = Some(export);
+        self.headless = true;
+        Ok(())
     }
 
-    fn move_and_print(&mut self, from: &Column, to: &Column) {
-        self.move_peg(from, to);
+    /// Keeps a list of every move made from now on in `history`.
+    pub fn record_history(&mut self) {
+        self.history = Some(Vec::new());
+    }
 
-        execute!(std::io::stdout(), Clear(All), MoveTo(0, 0)).unwrap();
-        println!("{}", self);
-        std::thread::sleep(std::time::Duration::from_millis(self.print_delay as u64));
+    /// Counts how often each cell of the board holds a disk from now on.
+    pub fn record_heatmap(&mut self) {
+        let mut heatmap = Heatmap::new(self.height);
+        heatmap.record(&self.state);
+        self.heatmap = Some(heatmap);
     }
 
-    fn get_layer_string(&self, layer: usize) -> String {
-        let mut layer_string = String::new();
+    /// In manual mode, waits for a keypress before each move from the first one on.
+    pub fn set_step_mode(&mut self, step_mode: StepMode) {
+        self.step_mode = step_mode;
+    }
 
-        let box_width = (self.height * 2 + 6) as usize;
+    /// Stops frames from being drawn in the terminal.
+    pub fn set_headless(&mut self) {
+        self.headless = true;
+    }
 
-        for col in 0..3 {
-            match self.state[col].get(layer) {
-                Some(value) => {
-                    let peg_string_length = (value * 2) as usize;
-                    let pad_spaces = (box_width - peg_string_length) / 2;
+    pub fn skip_stalled_frames(&mut self, skip: bool) {
+        self.watchdog = Watchdog::new(skip);
+    }
 
-                    layer_string.push_str(&" ".to_string().repeat(pad_spaces).to_owned());
+    /// Draws less of the animation in the terminal while it cannot keep up,
+    /// dropping colors, then in-between frames, then skipping frames.
+    pub fn adapt_fidelity(&mut self, adaptive: bool) {
+        self.watchdog.adaptive = adaptive;
+    }
 
-                    layer_string.push_str(&"■".to_string().repeat(peg_string_length).to_owned());
+    /// Stops the solve at the next move once `cancel` is set, e.g. from a
+    /// signal handler or another thread, with [`SolveError::Cancelled`].
+    pub fn set_cancel_token(&mut self, cancel: Arc<AtomicBool>) {
+        self.cancel = Some(cancel);
+    }
 
-                    layer_string.push_str(&" ".to_string().repeat(pad_spaces).to_owned());
-                }
-                None => layer_string.push_str(&" ".to_string().repeat(box_width).to_owned()),
-            }
-        }
-        layer_string.push_str(&"\n".to_string().to_owned());
-        layer_string
+    /// Makes the first `moves` moves of the solution without drawing them.
+    pub fn resume_from(&mut self, moves: u64) {
+        self.resume_from = moves;
     }
-}
 
-fn main() {
-    execute!(std::io::stdout(), DisableBlinking, Hide,).unwrap();
-    let args: Vec<String> = env::args().collect();
-    let (delay, height, loglevel) = get_parameters(args);
-    let mut tower = Tower::new(height, delay);
-    println!("{}", tower);
-    tower.solve();
-    match loglevel {
-        LogLevel::None => {}
-        LogLevel::Minimal => {
-            println!("Completed in {} moves", 2u32.pow(tower.height) - 1);
+    /// Holds the frame after move `moves` on screen for an extra `duration`.
+    pub fn pause_at(&mut self, moves: u64, duration: Duration) {
+        self.pauses.push((moves, duration));
+    }
+
+    /// The shortest the solve could have taken: every delay and pause, with
+    /// drawing taking no time at all. Moves skipped by a resume are not counted.
+    pub fn theoretical_time(&self) -> Duration {
+        let drawn_moves = self.moves.saturating_sub(self.resume_from);
+        Duration::from_millis(self.print_delay as u64 * drawn_moves) + self.paused
+    }
+
+    pub fn show_progress(&mut self, show: bool) {
+        self.show_progress = show;
+    }
+
+    /// Counts the disks already in their final place on the goal peg,
+    /// starting from the largest disk at the bottom.
+    pub fn placed_disks(&self) -> u32 {
+        self.state[self.goal.index()]
+            .iter()
+            .zip((1..=self.height).rev())
+            .take_while(|(disk, expected)| *disk == expected)
+            .count() as u32
+    }
+
+    pub fn set_tween(&mut self, tween: Tween) {
+        self.tween = tween;
+    }
+
+    /// Draws each listed disk size in its color, in the terminal and in ANSI frames.
+    pub fn set_disk_colors(&mut self, disk_colors: Vec<(u32, Color)>) {
+        self.disk_colors = disk_colors;
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    /// Chooses the recursive solution when the rules allow it, falling back
+    /// to searching for the shortest legal solution otherwise, and sets
+    /// `total_moves` without making any move. [`Tower::solve`] does this
+    /// itself when it has not been done already.
+    pub fn plan(&mut self) -> Result<(), String> {
+        if self.plan.is_some() {
+            return Ok(());
         }
-        LogLevel::All => {
-            println!("Completed in {} moves", 2u32.pow(tower.height) - 1);
-            println!("Tower height: {} pegs", tower.height);
-            println!("Delay: ~{}ms", delay);
+        if self
+            .rules
+            .allows_closed_form(self.height, self.start, self.goal)
+        {
+            self.total_moves = solver::optimal_moves(self.height);
+            self.plan = Some(Plan::ClosedForm);
+            return Ok(());
+        }
+        if self.height > MAX_SEARCH_HEIGHT {
+            return Err(format!(
+                "towers taller than {} disks cannot be searched",
+                MAX_SEARCH_HEIGHT
+            ));
+        }
+        if self.rules.search_states(self.height) > MAX_SEARCH_STATES {
+            return Err("the freeze lasts too many moves to search".to_string());
         }
+        let moves = solver::search(&self.state, self.goal, &self.rules)
+            .ok_or_else(|| "there is no solution under these rules".to_string())?;
+        self.total_moves = moves.len() as u64;
+        self.plan = Some(Plan::Search(moves));
+        Ok(())
     }
-}
 
-fn get_parameters(args: Vec<String>) -> (u32, u32, LogLevel) {
-    let (mut delay, mut height) = (DELAY_MS as u32, TOWER_SIZE as u32);
-    let mut log = LogLevel::Minimal;
-    if args.len() < 2 {
-        return (delay, height, log);
-    }
-    for arg_i in (0..(args.len() / 2)).map(|i| i * 2 + 1) {
-        match args.get(arg_i) {
-            Some(arg) => match &arg[..] {
-                "-H" => {
-                    display_help();
-                    process::exit(0);
-                }
-                "--help" => {
-                    display_help();
-                    process::exit(0);
-                }
-                "-D" => {
-                    delay = get_delay(&args, arg_i + 1);
-                }
-                "--delay" => {
-                    delay = get_delay(&args, arg_i + 1);
-                }
-                "-N" => {
-                    height = get_height(&args, arg_i + 1);
-                }
-                "--height" => {
-                    height = get_height(&args, arg_i + 1);
-                }
-                "-L" => {
-                    log = get_log(&args, arg_i + 1);
-                }
-                "--loglevel" => {
-                    log = get_log(&args, arg_i + 1);
-                }
-                _ => {
-                    println!("Unknown argument \"{}\"!", args[arg_i]);
-                    println!("Do -H or --help for more informatin.");
-                    process::exit(0);
+    pub fn solve(&mut self) -> Result<(), SolveError> {
+        self.plan().map_err(SolveError::Unsolvable)?;
+        // Searching and drawing the starting position are not part of the
+        // moves, so they are left out of the elapsed time and its overhead.
+        self.started = Instant::now();
+        match self.plan.take().unwrap() {
+            Plan::ClosedForm => {
+                let aux = Peg::other(self.start, self.goal);
+                self.move_stack(self.height, self.start, self.goal, aux)
+            }
+            Plan::Search(moves) => {
+                for (from, to) in moves {
+                    self.move_and_print(from, to)?;
                 }
-            },
-            None => break,
+                Ok(())
+            }
         }
     }
 
-    (delay, height, log)
-}
+    /// Draws the tower as it stands in the terminal.
+    pub fn draw(&self) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout();
+        writeln!(stdout, "{}", self.get_board_string(None, true))?;
+        stdout.flush()
+    }
 
-fn display_help() {
-    println!("Usage: hanoi [OPTION...]");
-    println!("Solves the tower of hanoi in your terminal!\n");
-    println!("Arguments:");
-    println!("-H, --help");
-    println!("\t Displays help");
-    println!("-D [value], --delay [value]");
-    println!("\tSets the delay between peg moves; [value] is a positive integer in milliseconds.");
-    println!("\tDefault value of 100");
-    println!("-N [value], --height [value]");
-    println!("\tSets the height of the tower; [value] is a positive integer.");
-    println!("-L [value], --loglevel [value]");
-    println!("\tSets the loglevel for the program (not capital sensitive).");
-    println!("\tPossible values are:");
-    println!("\t\t[None] - print nothing");
-    println!("\t\t[Minimal] - only print moves taken");
-    println!("\t\t[All] - print both moves taken, tower height and print delay");
-    println!("\tDefault value of [Minimal]");
-}
+    /// Makes the move if the rules allow it, returning whether it was made.
+    pub fn try_move(&mut self, from: Peg, to: Peg) -> bool {
+        if from.index() >= PEG_COUNT
+            || to.index() >= PEG_COUNT
+            || !self.rules.allows(&self.state, self.moves, from, to)
+        {
+            return false;
+        }
+        self.move_peg(from, to);
+        true
+    }
 
-fn get_delay(args: &Vec<String>, index: usize) -> u32 {
-    match args.get(index) {
-        None => {
-            println!("Please specify a value for delay!");
-            println!("Do -H or --help for more informatin.");
-            process::exit(0);
+    fn move_peg(&mut self, from: Peg, to: Peg) {
+        let val = self.state[from.index()].pop().unwrap();
+        self.state[to.index()].push(val);
+        self.moves += 1;
+        self.stats.record(val, from, to);
+        if let Some(history) = &mut self.history {
+            history.push((from, to));
         }
-        Some(string) => match string.parse::<u32>() {
-            Ok(val) => val,
-            Err(_) => {
-                println!("Please specify a valid value for delay!");
-                println!("Do -H or --help for more information.");
-                process::exit(0);
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record(&self.state);
+        }
+        if let Some(length) = self.sparkline {
+            self.recent_disks.push_back(val);
+            while self.recent_disks.len() > length {
+                self.recent_disks.pop_front();
             }
-        },
+        }
     }
-}
 
-fn get_height(args: &Vec<String>, index: usize) -> u32 {
-    match args.get(index) {
-        None => {
-            println!("Please specify a value for height!");
-            println!("Do -H or --help for more informatin.");
-            process::exit(0);
-        }
-        Some(string) => match string.parse::<u32>() {
-            Ok(val) => val,
-            Err(_) => {
-                println!("{} is not a valid value for height!", string);
-                println!("Please specify a valid value for height!");
-                println!("Do -H or --help for more information.");
-                process::exit(0);
+    fn move_stack(
+        &mut self,
+        size: u32,
+        start_col: Peg,
+        target_col: Peg,
+        aux_col: Peg,
+    ) -> Result<(), SolveError> {
+        if size > 0 {
+            if size > 1 {
+                self.sub_problems.push(SubProblem {
+                    size,
+                    from: start_col,
+                    to: target_col,
+                    via: aux_col,
+                });
             }
-        },
+            self.move_stack(size - 1, start_col, aux_col, target_col)?;
+            self.move_and_print(start_col, target_col)?;
+            self.move_stack(size - 1, aux_col, target_col, start_col)?;
+            if size > 1 {
+                self.sub_problems.pop();
+            }
+        }
+        Ok(())
     }
-}
 
-fn get_log(args: &Vec<String>, index: usize) -> LogLevel {
-    match args.get(index) {
-        None => {
-            println!("Please specify a value for log level!");
+    fn move_and_print(&mut self, from: Peg, to: Peg) -> Result<(), SolveError> {
+        if self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+        {
+            return Err(SolveError::Cancelled);
+        }
+        let delay = Duration::from_millis(self.print_delay as u64);
+        let tweened = self.tween.frames() > 0
+            && matches!(self.watchdog.fidelity, Fidelity::Full | Fidelity::Uncolored);
+        if self.moves < self.resume_from {
+            self.move_peg(from, to);
+            return Ok(());
+        }
+        if self.headless && self.frame_dump.is_none() && self.frame_export.is_none() {
+            self.move_peg(from, to);
+            self.clock.sleep(delay);
+            return Ok(());
+        }
+        if self.step_mode == StepMode::Manual && !self.headless {
+            self.wait_for_step()?;
+        }
+        let transit = if tweened {
+            self.get_transit(from, to)
+        } else {
+            Vec::new()
+        };
+        let frame_delay = delay / (transit.len() as u32 + 1);
+        if !transit.is_empty() {
+            let disk = self.state[from.index()].pop().unwrap();
+            for floating in transit {
+                if let Err(e) = self.emit_frame(from, to, Some(&floating), frame_delay) {
+                    self.state[from.index()].push(disk);
+                    return Err(e.into());
+                }
+            }
+            self.state[from.index()].push(disk);
+        }
+        self.move_peg(from, to);
+        self.emit_frame(from, to, None, frame_delay)?;
+        if let Some(export) = &self.frame_export {
+            self.exported_frames = export.frames_written();
+        }
+
+        let moves = self.moves;
+        for (_, duration) in self.pauses.iter().filter(|(at, _)| *at == moves) {
+            self.clock.sleep(*duration);
+            self.paused += *duration;
+        }
+        Ok(())
+    }
+
+    /// Waits for a key, cancelling the solve on q, Esc or Ctrl-C. The wait
+    /// counts as a pause, so it is not mistaken for rendering overhead.
+    fn wait_for_step(&mut self) -> Result<(), SolveError> {
+        let mut stdout = std::io::stdout();
+        write!(
+            stdout,
+            "Press any key for move {}, or q to stop",
+            self.moves + 1
+        )?;
+        stdout.flush()?;
+        let waiting = Instant::now();
+        let key = read_key()?;
+        self.paused += waiting.elapsed();
+        if is_quit_key(&key) {
+            writeln!(stdout)?;
+            return Err(SolveError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Lists where the top disk of `from` is drawn in each in-between frame
+    /// as it is lifted, slid across and dropped onto `to`. Frames that would
+    /// show the disk where the last one did, or where it rests before or after
+    /// the move, are left out, so there can be fewer than the tween asks for.
+    fn get_transit(&self, from: Peg, to: Peg) -> Vec<Floating> {
+        let disk = *self.state[from.index()].last().unwrap();
+        let air_row = self.height as usize;
+        let from_row = self.state[from.index()].len() - 1;
+        let to_row = self.state[to.index()].len();
+        let box_width = self.get_box_width();
+        let from_center = from.index() * box_width + box_width / 2;
+        let to_center = to.index() * box_width + box_width / 2;
+
+        let mut transit = Vec::new();
+        for i in 1..=self.tween.lift {
+            let t = ease_out(i as f64 / self.tween.lift as f64);
+            let row = lerp(from_row, air_row, t);
+            transit.push(Floating {
+                disk,
+                row,
+                center: from_center,
+            });
+        }
+        for i in 1..=self.tween.slide {
+            let t = ease_in_out(i as f64 / self.tween.slide as f64);
+            let center = lerp(from_center, to_center, t);
+            transit.push(Floating {
+                disk,
+                row: air_row,
+                center,
+            });
+        }
+        for i in 1..=self.tween.drop {
+            let t = ease_in(i as f64 / (self.tween.drop + 1) as f64);
+            let row = lerp(air_row, to_row, t);
+            transit.push(Floating {
+                disk,
+                row,
+                center: to_center,
+            });
+        }
+        transit.dedup();
+        transit.retain(|floating| {
+            (floating.row, floating.center) != (from_row, from_center)
+                && (floating.row, floating.center) != (to_row, to_center)
+        });
+        transit
+    }
+
+    /// Draws the board everywhere frames go, then waits `delay` before the next one.
+    /// Disk colors are left out of the frame dump and plain text exports.
+    fn emit_frame(
+        &mut self,
+        from: Peg,
+        to: Peg,
+        floating: Option<&Floating>,
+        delay: Duration,
+    ) -> std::io::Result<()> {
+        let mut status_bar = Vec::new();
+        if self.show_status {
+            status_bar.push(self.get_status_string(from, to));
+        }
+        if self.sparkline.is_some() {
+            status_bar.push(self.get_sparkline_string());
+        }
+        let with_status_bar = |board: String| {
+            if status_bar.is_empty() {
+                board
+            } else {
+                format!("{}\n{}", status_bar.join(" │ "), board)
+            }
+        };
+        let frame = with_status_bar(self.get_board_string(floating, false));
+        // Colors are only dropped from the terminal, never from an export.
+        let uncolored = self.frame_export.is_none() && self.watchdog.fidelity != Fidelity::Full;
+        let colored_frame = if self.disk_colors.is_empty() || uncolored {
+            None
+        } else {
+            Some(with_status_bar(self.get_board_string(floating, true)))
+        };
+        let colored_frame = colored_frame.as_ref().unwrap_or(&frame);
+        let skip = self.watchdog.should_skip(delay);
+        if !self.headless && !skip {
+            let render_start = Instant::now();
+            let mut stdout = std::io::stdout();
+            execute!(stdout, Clear(All), MoveTo(0, 0)).map_err(to_io_error)?;
+            writeln!(stdout, "{}", colored_frame)?;
+            stdout.flush()?;
+            self.frames_drawn += 1;
+            let render_time = render_start.elapsed();
+            let budget = Duration::from_millis(self.print_delay as u64)
+                / self.tween.frames().saturating_add(1);
+            self.watchdog.adapt(render_time, budget);
+            if self.watchdog.check(render_time, delay) {
+                eprintln!(
+                    "Warning: move {} took {}ms to draw with a delay of {}ms",
+                    self.moves,
+                    render_time.as_millis(),
+                    delay.as_millis()
+                );
+            }
+        }
+        if let Some(file) = &mut self.frame_dump {
+            writeln!(file, "{}", frame)?;
+        }
+        if let Some(export) = &mut self.frame_export {
+            match export.format() {
+                FrameFormat::Txt => export.write(&frame)?,
+                FrameFormat::Ansi => export.write(colored_frame)?,
+            }
+        }
+        if !skip {
+            self.clock.sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// Draws the board, with `floating` drawn over it when a disk is mid-move,
+    /// and the disks in their colors if `colored` is set.
+    /// Tweened towers get an extra row of air above the pegs to move through.
+    fn get_board_string(&self, floating: Option<&Floating>, colored: bool) -> String {
+        let mut rows = self.height as usize;
+        if self.tween.frames() > 0 {
+            rows += 1;
+        }
+        let mut board_string = String::new();
+        for layer in (0..rows).rev() {
+            let floating = floating.filter(|floating| floating.row == layer);
+            board_string.push_str(&self.get_layer_string(layer, floating, colored));
+        }
+        board_string.push_str(&self.get_label_string());
+        if self.show_progress {
+            board_string.push_str(&self.get_progress_string());
+        }
+        board_string
+    }
+
+    fn get_status_string(&self, from: Peg, to: Peg) -> String {
+        let locale = &self.locale;
+        let elapsed = self.clock.elapsed();
+        let per_second = |count: u64| {
+            let seconds = elapsed.as_secs_f64();
+            if seconds > 0.0 {
+                locale.format_number((count as f64 / seconds).round() as u64)
+            } else {
+                "-".to_string()
+            }
+        };
+        self.status_format.render(|field| match field {
+            StatusField::Action => self.get_action_string(from, to),
+            StatusField::Move => locale.format_number(self.moves),
+            StatusField::Total => locale.format_number(self.total_moves),
+            StatusField::Percent => {
+                locale.format_percent(self.moves as f64 / self.total_moves.max(1) as f64)
+            }
+            StatusField::Elapsed => locale.format_duration(elapsed),
+            StatusField::Speed => per_second(self.moves.saturating_sub(self.resume_from)),
+            StatusField::Fps => per_second(self.frames_drawn),
+        })
+    }
+
+    fn get_action_string(&self, from: Peg, to: Peg) -> String {
+        match self.sub_problems.last() {
+            Some(sub_problem) => format!(
+                "Moving {}-stack from {} to {} via {}",
+                sub_problem.size, sub_problem.from, sub_problem.to, sub_problem.via
+            ),
+            None => format!("Moving disk from {} to {}", from, to),
+        }
+    }
+
+    /// Draws one bar per recent move, taller for larger disks.
+    fn get_sparkline_string(&self) -> String {
+        self.recent_disks
+            .iter()
+            .map(|disk| {
+                let steps = SPARKLINE_BARS.len() - 1;
+                let level = (*disk as usize - 1) * steps / (self.height as usize - 1).max(1);
+                SPARKLINE_BARS[level]
+            })
+            .collect()
+    }
+
+    fn get_box_width(&self) -> usize {
+        (self.height * 2 + 6) as usize
+    }
+
+    fn get_label_string(&self) -> String {
+        let box_width = self.get_box_width();
+        let mut label_string = String::new();
+        for peg in Peg::all(PEG_COUNT) {
+            label_string.push_str(&format!("{:^width$}", peg.name(), width = box_width));
+        }
+        label_string.push('\n');
+        label_string
+    }
+
+    fn get_progress_string(&self) -> String {
+        let box_width = self.get_box_width();
+        let placed = self.placed_disks();
+        let meter = format!(
+            "[{}{}] {}/{}",
+            "■".repeat(placed as usize),
+            "·".repeat((self.height - placed) as usize),
+            placed,
+            self.height
+        );
+        let mut progress_string = String::new();
+        for peg in Peg::all(PEG_COUNT) {
+            if peg == self.goal {
+                progress_string.push_str(&format!("{:^width$}", meter, width = box_width));
+            } else {
+                progress_string.push_str(&" ".repeat(box_width));
+            }
+        }
+        progress_string.push('\n');
+        progress_string
+    }
+
+    fn get_cell_string(&self, peg: Peg, layer: usize) -> String {
+        let box_width = self.get_box_width();
+        match self.state[peg.index()].get(layer) {
+            Some(value) => {
+                let peg_string_length = (value * 2) as usize;
+                let pad_spaces = (box_width - peg_string_length) / 2;
+                let block = if self.rules.is_frozen(*value, self.moves) {
+                    "▒"
+                } else {
+                    "■"
+                };
+
+                let mut cell_string = " ".repeat(pad_spaces);
+                cell_string.push_str(&block.repeat(peg_string_length));
+                cell_string.push_str(&" ".repeat(pad_spaces));
+                cell_string
+            }
+            None => match self.rules.capacities[peg.index()] {
+                Some(capacity) if capacity as usize == layer => {
+                    format!(
+                        "{:┄^width$}",
+                        format!(" max {} ", capacity),
+                        width = box_width
+                    )
+                }
+                _ => " ".repeat(box_width),
+            },
+        }
+    }
+
+    /// The color given to `disk` with --disk-color, the last one winning.
+    fn get_disk_color(&self, disk: u32) -> Option<Color> {
+        self.disk_colors
+            .iter()
+            .rev()
+            .find(|(size, _)| *size == disk)
+            .map(|(_, color)| *color)
+    }
+
+    fn get_layer_string(&self, layer: usize, floating: Option<&Floating>, colored: bool) -> String {
+        // Each character remembers the disk it belongs to, so that disks can
+        // be colored after the floating one has been drawn over the row.
+        let mut cells: Vec<(char, Option<u32>)> = Vec::new();
+        for peg in Peg::all(PEG_COUNT) {
+            let disk = self.state[peg.index()].get(layer).copied();
+            for c in self.get_cell_string(peg, layer).chars() {
+                cells.push((c, disk.filter(|_| c != ' ')));
+            }
+        }
+        if let Some(floating) = floating {
+            let width = (floating.disk * 2) as usize;
+            let left = floating.center - width / 2;
+            for cell in cells.iter_mut().skip(left).take(width) {
+                *cell = ('■', Some(floating.disk));
+            }
+        }
+
+        let mut layer_string = String::new();
+        let mut run = String::new();
+        let mut run_color = None;
+        for (c, disk) in cells {
+            let color = disk
+                .filter(|_| colored)
+                .and_then(|disk| self.get_disk_color(disk));
+            if color != run_color {
+                push_colored(&mut layer_string, &run, run_color);
+                run.clear();
+                run_color = color;
+            }
+            run.push(c);
+        }
+        push_colored(&mut layer_string, &run, run_color);
+        layer_string.push('\n');
+        layer_string
+    }
+}
+
+/// Why a tower could not be solved to the end.
+enum SolveError {
+    /// The rules leave no way to reach the goal, or one too costly to find.
+    Unsolvable(String),
+    /// Drawing or saving a frame failed partway through.
+    Output(std::io::Error),
+    /// The cancellation token was set, so the solve stopped between two moves.
+    Cancelled,
+}
+
+impl From<std::io::Error> for SolveError {
+    fn from(e: std::io::Error) -> Self {
+        SolveError::Output(e)
+    }
+}
+
+/// Waits for a single keypress, without the user having to press Enter.
+fn read_key() -> std::io::Result<KeyEvent> {
+    enable_raw_mode().map_err(to_io_error)?;
+    let key = loop {
+        match event::read() {
+            Ok(Event::Key(key)) => break Ok(key),
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode().map_err(to_io_error)?;
+    key.map_err(to_io_error)
+}
+
+/// Whether `key` is q, Esc or Ctrl-C, which raw mode no longer turns into a signal.
+fn is_quit_key(key: &KeyEvent) -> bool {
+    let interrupted =
+        key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+    interrupted || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+}
+
+/// Gives up on an interactive screen once the terminal cannot be used,
+/// where there is no solve to save a checkpoint of.
+fn exit_with_terminal_error(e: std::io::Error) -> ! {
+    eprintln!("Could not use the terminal: {}", e);
+    process::exit(1);
+}
+
+fn to_io_error(e: crossterm::ErrorKind) -> std::io::Error {
+    match e {
+        crossterm::ErrorKind::IoError(e) => e,
+        e => std::io::Error::other(e.to_string()),
+    }
+}
+
+fn push_colored(string: &mut String, text: &str, color: Option<Color>) {
+    match color {
+        Some(color) => string.push_str(&style(text).with(color).to_string()),
+        None => string.push_str(text),
+    }
+}
+
+/// Lists the first `count` moves that take a `height` tall tower from `start`
+/// to `goal`, without working out the rest of the solution.
+fn solution_moves(height: u32, start: Peg, goal: Peg, count: usize) -> Vec<(Peg, Peg)> {
+    fn push_moves(
+        moves: &mut Vec<(Peg, Peg)>,
+        count: usize,
+        size: u32,
+        from: Peg,
+        to: Peg,
+        via: Peg,
+    ) {
+        if size > 0 && moves.len() < count {
+            push_moves(moves, count, size - 1, from, via, to);
+            if moves.len() < count {
+                moves.push((from, to));
+            }
+            push_moves(moves, count, size - 1, via, to, from);
+        }
+    }
+    let mut moves = Vec::new();
+    push_moves(
+        &mut moves,
+        count,
+        height,
+        start,
+        goal,
+        Peg::other(start, goal),
+    );
+    moves
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut config = get_parameters(args);
+    match &config.command {
+        Command::Solve => {
+            if config.calibrate && config.summary_format == SummaryFormat::Text {
+                calibrate::run(&mut config);
+            }
+            cancel_on_interrupt(&config);
+            run_solve(&config);
+        }
+        Command::Frames => {
+            cancel_on_interrupt(&config);
+            run_frames(config);
+        }
+        Command::Diff => run_diff(config),
+        Command::Fuzz => fuzz::run_stdin(),
+        Command::Batch => {
+            cancel_on_interrupt(&config);
+            batch::run(&config);
+        }
+        Command::Selftest => selftest::run(&config),
+        Command::Symmetry => symmetry::run(&config),
+        Command::Setup => {
+            setup::run(&mut config);
+            if config.calibrate && config.summary_format == SummaryFormat::Text {
+                calibrate::run(&mut config);
+            }
+            cancel_on_interrupt(&config);
+            run_solve(&config);
+        }
+        Command::Scenario(path) => {
+            let path = path.to_owned();
+            cancel_on_interrupt(&config);
+            scenario::run_file(&path, &mut config);
+        }
+    }
+}
+
+/// Makes Ctrl-C and SIGTERM, and closing the terminal where there is SIGHUP,
+/// set the cancellation token instead of killing the process, so a solve
+/// stops at the next move and saves how far it got.
+fn cancel_on_interrupt(config: &Config) {
+    let mut signals = vec![signal_hook::SIGINT, signal_hook::SIGTERM];
+    #[cfg(unix)]
+    signals.push(signal_hook::SIGHUP);
+    for signal in signals.iter() {
+        if let Err(e) = signal_hook::flag::register(*signal, Arc::clone(&config.cancel)) {
+            eprintln!("Warning: could not catch signal {}: {}", signal, e);
+        }
+    }
+}
+
+fn run_solve(config: &Config) {
+    let mut tower = build_tower(config, config.height);
+    solve_or_exit(config, &mut tower);
+    print_summary(config, &tower);
+}
+
+/// Solves the tower, exiting with a message if it cannot be finished. When
+/// drawing fails a checkpoint is saved first, so the solve can be resumed.
+fn solve_or_exit(config: &Config, tower: &mut Tower) {
+    match tower.solve() {
+        Ok(()) => finish_solve(config),
+        Err(error) => exit_with_solve_error(config, tower, error),
+    }
+}
+
+/// Clears the checkpoint a solve that has just finished was resumed from.
+fn finish_solve(config: &Config) {
+    if let Some(checkpoint) = &config.resumed_checkpoint {
+        remove_finished_checkpoint(checkpoint, &session_path());
+        remove_finished_checkpoint(checkpoint, Path::new(&config.checkpoint));
+    }
+}
+
+/// Reports why `tower` could not be solved and exits, saving a checkpoint
+/// first if the solve could be picked up again.
+fn exit_with_solve_error(config: &Config, tower: &Tower, error: SolveError) -> ! {
+    match error {
+        SolveError::Unsolvable(e) => {
+            save_manifest(config, tower);
+            println!("Could not solve the tower: {}!", e);
+            process::exit(0);
+        }
+        SolveError::Output(e) => exit_with_checkpoint(config, tower, e),
+        SolveError::Cancelled => {
+            let saved = write_checkpoint(config, tower);
+            print_summary(config, tower);
+            report_checkpoint(config, saved);
+            process::exit(130);
+        }
+    }
+}
+
+/// Saves a checkpoint and the manifest before reporting anything, as the
+/// terminal may be gone.
+fn exit_with_checkpoint(config: &Config, tower: &Tower, error: std::io::Error) -> ! {
+    let saved = write_checkpoint(config, tower);
+    save_manifest(config, tower);
+    eprintln!(
+        "Could not draw the tower after {} moves: {}",
+        tower.moves, error
+    );
+    report_checkpoint(config, saved);
+    process::exit(1);
+}
+
+/// Tells the user on stderr whether the checkpoint was saved, and how to resume from it.
+fn report_checkpoint(config: &Config, saved: std::io::Result<()>) {
+    match saved {
+        Ok(()) => eprintln!(
+            "Progress was saved; run `hanoi continue` or `hanoi {}--resume {}` to continue.",
+            match config.command {
+                Command::Frames => "frames ",
+                _ => "",
+            },
+            config.checkpoint
+        ),
+        Err(e) => eprintln!(
+            "Could not save progress to \"{}\": {}",
+            config.checkpoint, e
+        ),
+    }
+}
+
+/// A `height` tall tower with every setting from `config` that is not about
+/// where its frames go.
+fn configured_tower(config: &Config, height: u32, clock: Clock) -> Tower {
+    let mut tower = Tower::new(height, config.delay, config.start, config.goal, clock);
+    tower.show_status(config.show_status);
+    tower.set_status_format(config.status_format.clone());
+    tower.set_locale(config.locale);
+    tower.show_sparkline(config.sparkline);
+    tower.show_progress(config.show_progress);
+    tower.skip_stalled_frames(config.skip_stalled);
+    tower.adapt_fidelity(config.adaptive);
+    tower.set_step_mode(config.step_mode);
+    tower.set_rules(config.rules());
+    tower.set_tween(config.tween);
+    tower.set_disk_colors(config.disk_colors.clone());
+    tower.resume_from(config.resume_from);
+    tower.set_cancel_token(Arc::clone(&config.cancel));
+    if config.heatmap {
+        tower.record_heatmap();
+    }
+    tower
+}
+
+/// Sets up a `height` tall tower for an animated solve and draws its starting position.
+/// With a JSON summary nothing is drawn, so stdout carries only the summary.
+fn build_tower(config: &Config, height: u32) -> Tower {
+    let headless = config.summary_format == SummaryFormat::Json;
+    let mut tower = configured_tower(config, height, Clock::new(config.fast_forward));
+    if let Some(path) = &config.dump_frames {
+        let file = File::create(path).and_then(|mut file| {
+            writeln!(file, "{}", recording_header(config, height))?;
+            Ok(file)
+        });
+        match file {
+            Ok(file) => tower.dump_frames_to(file),
+            Err(e) => {
+                println!("Could not create frame dump file \"{}\": {}", path, e);
+                process::exit(0);
+            }
+        }
+    }
+    if headless {
+        tower.set_headless();
+        return tower;
+    }
+    let drawn = execute!(std::io::stdout(), DisableBlinking, Hide)
+        .map_err(to_io_error)
+        .and_then(|()| tower.draw());
+    if let Err(e) = drawn {
+        exit_with_checkpoint(config, &tower, e);
+    }
+    tower
+}
+
+/// Reports on a finished or stopped run, writing the manifest if one was asked for.
+fn print_summary(config: &Config, tower: &Tower) {
+    save_manifest(config, tower);
+    let locale = &config.locale;
+    let outcome = if tower.placed_disks() == tower.height {
+        "Completed in"
+    } else {
+        "Stopped after"
+    };
+    match (&config.log, &config.summary_format) {
+        (LogLevel::None, _) => {}
+        (_, SummaryFormat::Json) => println!("{}", summary_json(config, tower)),
+        (LogLevel::Minimal, _) => {
+            println!("{} {} moves", outcome, locale.format_number(tower.moves));
+        }
+        (LogLevel::All, _) => {
+            println!("{} {} moves", outcome, locale.format_number(tower.moves));
+            println!("Tower height: {} pegs", tower.height);
+            println!("Moved from peg {} to peg {}", tower.start, tower.goal);
+            println!("Delay: ~{}ms", locale.format_number(config.delay as u64));
+            let elapsed = tower.started.elapsed();
+            println!("Elapsed time: {}", locale.format_duration(elapsed));
+            if config.fast_forward {
+                println!(
+                    "Simulated time: {}",
+                    locale.format_duration(tower.clock.elapsed())
+                );
+            } else {
+                let theoretical = tower.theoretical_time();
+                println!(
+                    "Theoretical minimum: {}",
+                    locale.format_duration(theoretical)
+                );
+                if theoretical > Duration::from_millis(0) {
+                    let overhead = elapsed.checked_sub(theoretical).unwrap_or_default();
+                    println!(
+                        "Rendering overhead: {}",
+                        locale.format_percent(overhead.as_secs_f64() / theoretical.as_secs_f64())
+                    );
+                }
+            }
+            let peg_counts = |counts: &[u64; PEG_COUNT]| {
+                Peg::all(PEG_COUNT)
+                    .map(|peg| format!("{} {}", peg, locale.format_number(counts[peg.index()])))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            println!("Moves from each peg: {}", peg_counts(&tower.stats.from_peg));
+            println!("Moves to each peg: {}", peg_counts(&tower.stats.to_peg));
+            let disk_counts: Vec<String> = tower
+                .stats
+                .disk_moves
+                .iter()
+                .zip(1..)
+                .map(|(moves, disk)| format!("{} {}", disk, locale.format_number(*moves)))
+                .collect();
+            println!("Moves of each disk: {}", disk_counts.join(", "));
+            if tower.watchdog.stalls > 0 {
+                println!(
+                    "Stalled frames: {}",
+                    locale.format_number(tower.watchdog.stalls as u64)
+                );
+            }
+            if tower.watchdog.skipped_frames > 0 {
+                println!(
+                    "Skipped frames: {}",
+                    locale.format_number(tower.watchdog.skipped_frames as u64)
+                );
+            }
+            if tower.watchdog.downgrades > 0 {
+                println!(
+                    "Fidelity downgrades: {}",
+                    locale.format_number(tower.watchdog.downgrades as u64)
+                );
+            }
+        }
+    }
+    if let (Some(heatmap), SummaryFormat::Text) = (&tower.heatmap, &config.summary_format) {
+        if !matches!(config.log, LogLevel::None) {
+            println!("\nShare of the run each cell held a disk:");
+            println!("{}", heatmap.render(tower.get_box_width(), locale));
+        }
+    }
+}
+
+/// The same statistics as the full text summary, as one JSON object.
+/// Efficiency compares the moves made with the classic puzzle's minimum.
+fn summary_json(config: &Config, tower: &Tower) -> Json {
+    // The shortest solution under these rules, which freezes can lengthen.
+    let optimal = tower.total_moves;
+    let mut variant = vec![(
+        "name",
+        Json::String(
+            match (config.capacities.iter().any(Option::is_some), config.frozen) {
+                (false, None) => "classic",
+                (true, None) => "capacity",
+                (false, Some(_)) => "freeze",
+                (true, Some(_)) => "capacity and freeze",
+            }
+            .to_string(),
+        ),
+    )];
+    let capacities: Vec<Json> = Peg::all(PEG_COUNT)
+        .filter_map(|peg| {
+            config.capacities[peg.index()].map(|capacity| {
+                Json::Object(vec![
+                    ("peg", Json::String(peg.to_string())),
+                    ("capacity", Json::Integer(capacity as u64)),
+                ])
+            })
+        })
+        .collect();
+    if !capacities.is_empty() {
+        variant.push(("capacities", Json::Array(capacities)));
+    }
+    if let Some(frozen) = config.frozen {
+        variant.push((
+            "freeze",
+            Json::Object(vec![
+                ("disk", Json::Integer(frozen.disk as u64)),
+                ("moves", Json::Integer(frozen.moves)),
+            ]),
+        ));
+    }
+
+    let mut time = vec![(
+        "elapsed_seconds",
+        Json::Float(tower.started.elapsed().as_secs_f64()),
+    )];
+    if config.fast_forward {
+        time.push((
+            "simulated_seconds",
+            Json::Float(tower.clock.elapsed().as_secs_f64()),
+        ));
+    } else {
+        time.push((
+            "theoretical_seconds",
+            Json::Float(tower.theoretical_time().as_secs_f64()),
+        ));
+    }
+
+    let pegs = Peg::all(PEG_COUNT)
+        .map(|peg| {
+            Json::Object(vec![
+                ("peg", Json::String(peg.to_string())),
+                (
+                    "moves_from",
+                    Json::Integer(tower.stats.from_peg[peg.index()]),
+                ),
+                ("moves_to", Json::Integer(tower.stats.to_peg[peg.index()])),
+            ])
+        })
+        .collect();
+    let disks = tower
+        .stats
+        .disk_moves
+        .iter()
+        .zip(1..)
+        .map(|(moves, disk)| {
+            Json::Object(vec![
+                ("disk", Json::Integer(disk)),
+                ("moves", Json::Integer(*moves)),
+            ])
+        })
+        .collect();
+
+    let mut summary = vec![
+        (
+            "completed",
+            Json::Bool(tower.placed_disks() == tower.height),
+        ),
+        ("moves", Json::Integer(tower.moves)),
+        ("optimal_moves", Json::Integer(optimal)),
+        (
+            "efficiency",
+            Json::Float(optimal as f64 / tower.moves as f64),
+        ),
+        ("height", Json::Integer(tower.height as u64)),
+        ("start", Json::String(tower.start.to_string())),
+        ("goal", Json::String(tower.goal.to_string())),
+        ("delay_ms", Json::Integer(config.delay as u64)),
+        ("fast_forward", Json::Bool(config.fast_forward)),
+        ("variant", Json::Object(variant)),
+        ("time", Json::Object(time)),
+        ("pegs", Json::Array(pegs)),
+        ("disks", Json::Array(disks)),
+        (
+            "stalled_frames",
+            Json::Integer(tower.watchdog.stalls as u64),
+        ),
+        (
+            "skipped_frames",
+            Json::Integer(tower.watchdog.skipped_frames as u64),
+        ),
+        (
+            "fidelity_downgrades",
+            Json::Integer(tower.watchdog.downgrades as u64),
+        ),
+    ];
+    if let Some(heatmap) = &tower.heatmap {
+        summary.push(("heatmap", heatmap.to_json()));
+    }
+    Json::Object(summary)
+}
+
+fn run_frames(config: Config) {
+    let out = match &config.frames_out {
+        Some(out) => out,
+        None => {
+            println!("Please specify an output directory with --out!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    };
+    let mut tower = configured_tower(&config, config.height, Clock::new(true));
+    // The file names are padded to fit every frame, so the length of the
+    // solution has to be known before the first one is written.
+    if let Err(e) = tower.plan() {
+        save_manifest(&config, &tower);
+        println!("Could not solve the tower: {}!", e);
+        process::exit(0);
+    }
+    let header = recording_header(&config, config.height);
+    let export = FrameExport::create(
+        Path::new(out),
+        config.frames_format,
+        tower
+            .total_moves
+            .saturating_mul(config.tween.frames() as u64 + 1)
+            .saturating_add(1),
+    )
+    .and_then(|mut export| {
+        export.write_metadata(&header)?;
+        export.resume_at(config.resume_frames);
+        tower.export_frames(export)
+    });
+    if let Err(e) = export {
+        save_manifest(&config, &tower);
+        println!("Could not write frames to \"{}\": {}", out, e);
+        process::exit(0);
+    }
+    solve_or_exit(&config, &mut tower);
+    save_manifest(&config, &tower);
+    match config.log {
+        LogLevel::None => {}
+        _ => {
+            let written = tower.frame_export.as_ref().unwrap().frames_written();
+            println!(
+                "Wrote {} frames to {}",
+                config.locale.format_number(written as u64),
+                out
+            );
+        }
+    }
+}
+
+fn run_diff(config: Config) {
+    let towers = match &config.diff_input {
+        Some(input) => input.towers(config.height, config.start, config.goal),
+        None => {
+            println!("Please specify what to compare with --moves or --states!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    };
+    match towers {
+        Ok((left, right)) => {
+            let input = config.diff_input.as_ref().unwrap();
+            print!("{}", diff::render_diff(&left, &right, input.titles()));
+        }
+        Err(e) => {
+            println!("Could not compare states: {}!", e);
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+/// Describes the settings a recording was made with, one `key: value` per line.
+fn recording_header(config: &Config, height: u32) -> String {
+    let mut header = String::from("# hanoi recording\n");
+    header.push_str(&format!("version: {}\n", env!("CARGO_PKG_VERSION")));
+    header.push_str(&format!("command: {}\n", config.command));
+    header.push_str(&format!("height: {}\n", height));
+    header.push_str(&format!("start: {}\n", config.start));
+    header.push_str(&format!("goal: {}\n", config.goal));
+    header.push_str(&format!("delay: {}\n", config.delay));
+    header.push_str(&format!("status: {}\n", config.show_status));
+    header.push_str(&format!("status-format: {}\n", config.status_format));
+    header.push_str(&format!("progress: {}\n", config.show_progress));
+    if let Some(length) = config.sparkline {
+        header.push_str(&format!("sparkline: {}\n", length));
+    }
+    for peg in Peg::all(PEG_COUNT) {
+        if let Some(capacity) = config.capacities[peg.index()] {
+            header.push_str(&format!("capacity: {}={}\n", peg, capacity));
+        }
+    }
+    if config.tween.frames() > 0 {
+        let tween = config.tween;
+        header.push_str(&format!(
+            "tween: {},{},{}\n",
+            tween.lift, tween.slide, tween.drop
+        ));
+    }
+    if let Some(frozen) = config.frozen {
+        header.push_str(&format!("freeze: {}:{}\n", frozen.disk, frozen.moves));
+    }
+    for (disk, color) in &config.disk_colors {
+        header.push_str(&format!("disk-color: {}={}\n", disk, format_color(*color)));
+    }
+    if let Command::Frames = config.command {
+        header.push_str(&format!("format: {}\n", config.frames_format.name()));
+        if let Some(out) = &config.frames_out {
+            header.push_str(&format!("out: {}\n", out));
+        }
+    }
+    header
+}
+
+/// Restores the settings written by [`recording_header`], returning the
+/// number of moves recorded by a checkpoint (0 if there is none).
+fn apply_recording_header(config: &mut Config, header: &str) -> Result<u64, String> {
+    let mut moves = 0;
+    for line in header.lines() {
+        let (key, value) = match line.split_once(": ") {
+            Some(pair) if !line.starts_with('#') => pair,
+            _ => continue,
+        };
+        let invalid = || format!("{} is not a valid value for {}", value, key);
+        let peg = |value: &str| Peg::parse_within(value, PEG_COUNT).ok();
+        match key {
+            "height" => config.height = value.parse().map_err(|_| invalid())?,
+            "start" => config.start = peg(value).ok_or_else(invalid)?,
+            "goal" => config.goal = peg(value).ok_or_else(invalid)?,
+            "delay" => config.delay = value.parse().map_err(|_| invalid())?,
+            "status" => config.show_status = value.parse().map_err(|_| invalid())?,
+            "status-format" => config.status_format = value.parse().map_err(|_| invalid())?,
+            "progress" => config.show_progress = value.parse().map_err(|_| invalid())?,
+            "sparkline" => config.sparkline = Some(value.parse().map_err(|_| invalid())?),
+            "capacity" => {
+                let (peg, capacity) = parse_capacity(value).ok_or_else(invalid)?;
+                config.capacities[peg.index()] = Some(capacity);
+            }
+            "tween" => config.tween = parse_tween(value).ok_or_else(invalid)?,
+            "freeze" => config.frozen = Some(parse_frozen(value).ok_or_else(invalid)?),
+            "disk-color" => config
+                .disk_colors
+                .push(parse_disk_color(value).ok_or_else(invalid)?),
+            "format" => config.frames_format = value.parse().map_err(|_| invalid())?,
+            "out" => config.frames_out = Some(value.to_string()),
+            "moves" => moves = value.parse().map_err(|_| invalid())?,
+            "frames" => config.resume_frames = value.parse().map_err(|_| invalid())?,
+            // The rest are only written to manifests.
+            "heights" => config.heights = parse_heights(value).ok_or_else(invalid)?,
+            "loglevel" => config.log = LogLevel::parse(value).ok_or_else(invalid)?,
+            "summary-format" => {
+                config.summary_format = SummaryFormat::parse(value).ok_or_else(invalid)?
+            }
+            "fast-forward" => config.fast_forward = value.parse().map_err(|_| invalid())?,
+            "skip-stalled" => config.skip_stalled = value.parse().map_err(|_| invalid())?,
+            "adaptive" => config.adaptive = value.parse().map_err(|_| invalid())?,
+            "calibrate" => config.calibrate = value.parse().map_err(|_| invalid())?,
+            "step-mode" => config.step_mode = StepMode::parse(value).ok_or_else(invalid)?,
+            "heatmap" => config.heatmap = value.parse().map_err(|_| invalid())?,
+            "locale" => config.locale = Locale::from_tag(value).ok_or_else(invalid)?,
+            _ => {}
+        }
+    }
+    Ok(moves)
+}
+
+/// Writes the manifest of a run to the file given with --manifest, if any;
+/// `tower` is the last tower solved.
+fn save_manifest(config: &Config, tower: &Tower) {
+    if let Some(path) = &config.manifest {
+        if let Err(e) = std::fs::write(path, manifest(config, tower)) {
+            eprintln!("Could not write manifest \"{}\": {}", path, e);
+        }
+    }
+}
+
+/// Everything needed to reproduce a run: the recording header, which --resume
+/// reads back, followed by the remaining settings and what was detected about
+/// the system and terminal.
+fn manifest(config: &Config, tower: &Tower) -> String {
+    let mut manifest =
+        recording_header(config, tower.height).replacen("# hanoi recording", "# hanoi manifest", 1);
+    let mut settings = Vec::new();
+    if let Command::Batch = config.command {
+        let heights: Vec<String> = config.heights.iter().map(u32::to_string).collect();
+        settings.push(("heights", heights.join(",")));
+    }
+    settings.extend(vec![
+        ("made", tower.moves.to_string()),
+        (
+            "completed",
+            (tower.placed_disks() == tower.height).to_string(),
+        ),
+        ("resumed-from", config.resume_from.to_string()),
+        ("loglevel", config.log.name().to_string()),
+        ("summary-format", config.summary_format.name().to_string()),
+        ("fast-forward", config.fast_forward.to_string()),
+        ("skip-stalled", config.skip_stalled.to_string()),
+        ("adaptive", config.adaptive.to_string()),
+        ("calibrate", config.calibrate.to_string()),
+        ("step-mode", config.step_mode.name().to_string()),
+        ("heatmap", config.heatmap.to_string()),
+        ("checkpoint", config.checkpoint.clone()),
+        ("locale", config.locale.tag().to_string()),
+        ("crossterm", env!("CROSSTERM_VERSION").to_string()),
+        ("os", format!("{} {}", env::consts::OS, env::consts::ARCH)),
+        (
+            "stdout-terminal",
+            std::io::stdout().is_terminal().to_string(),
+        ),
+        ("stdin-terminal", std::io::stdin().is_terminal().to_string()),
+    ]);
+    if let Ok((columns, rows)) = crossterm::terminal::size() {
+        settings.push(("terminal-size", format!("{}x{}", columns, rows)));
+    }
+    for (key, value) in settings {
+        manifest.push_str(&format!("{}: {}\n", key, value));
+    }
+    for name in ["TERM", "COLORTERM", "LC_ALL", "LC_NUMERIC", "LANG"].iter() {
+        if let Some(value) = env::var_os(name) {
+            manifest.push_str(&format!("env {}: {}\n", name, value.to_string_lossy()));
+        }
+    }
+    manifest
+}
+
+/// Saves how far a solve got so it can be picked up again with --resume.
+/// A copy is also kept as the session file, for `hanoi continue` to find
+/// from any directory; failing to write that copy is not an error.
+fn write_checkpoint(config: &Config, tower: &Tower) -> std::io::Result<()> {
+    let mut checkpoint = recording_header(config, tower.height);
+    checkpoint.push_str(&format!("moves: {}\n", tower.moves));
+    if tower.frame_export.is_some() {
+        checkpoint.push_str(&format!("frames: {}\n", tower.exported_frames));
+    }
+    std::fs::write(session_path(), &checkpoint).ok();
+    std::fs::write(&config.checkpoint, checkpoint)
+}
+
+/// Removes the checkpoint at `path` if it still holds `checkpoint`, the one
+/// a solve that has now finished was resumed from. Checkpoints of other
+/// solves are left for `hanoi continue` or --resume to pick up.
+fn remove_finished_checkpoint(checkpoint: &str, path: &Path) {
+    if std::fs::read_to_string(path).is_ok_and(|saved| saved == checkpoint) {
+        std::fs::remove_file(path).ok();
+    }
+}
+
+/// Where the last unfinished solve is kept: the home directory if there is
+/// one, otherwise the current directory.
+fn session_path() -> PathBuf {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(SESSION_FILE)
+}
+
+/// Restores the settings and progress saved in a checkpoint at `path`.
+fn resume_from_checkpoint(config: &mut Config, path: &Path) -> Result<(), String> {
+    let checkpoint = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    config.resume_from = apply_recording_header(config, &checkpoint)?;
+    config.resumed_checkpoint = Some(checkpoint);
+    Ok(())
+}
+
+fn get_parameters(args: Vec<String>) -> Config {
+    let mut config = Config {
+        command: Command::Solve,
+        delay: DELAY_MS as u32,
+        height: TOWER_SIZE,
+        log: LogLevel::Minimal,
+        fast_forward: false,
+        dump_frames: None,
+        show_status: false,
+        status_format: StatusTemplate::action(),
+        show_progress: false,
+        sparkline: None,
+        skip_stalled: false,
+        adaptive: false,
+        step_mode: StepMode::Auto,
+        calibrate: false,
+        heatmap: false,
+        start: Peg::new(0),
+        goal: Peg::new(PEG_COUNT - 1),
+        frames_out: None,
+        frames_format: FrameFormat::Txt,
+        diff_input: None,
+        locale: Locale::from_env(),
+        capacities: [None; PEG_COUNT],
+        frozen: None,
+        tween: Tween::none(),
+        disk_colors: Vec::new(),
+        heights: Vec::new(),
+        animate: false,
+        summary_format: SummaryFormat::Text,
+        checkpoint: CHECKPOINT_FILE.to_string(),
+        resume_from: 0,
+        resume_frames: 0,
+        resumed_checkpoint: None,
+        manifest: None,
+        cancel: Arc::new(AtomicBool::new(false)),
+    };
+    let mut arg_i = 1;
+    match args.get(1).map(|arg| &arg[..]) {
+        Some("frames") => {
+            config.command = Command::Frames;
+            arg_i += 1;
+        }
+        Some("diff") => {
+            config.command = Command::Diff;
+            arg_i += 1;
+        }
+        Some("setup") => {
+            config.command = Command::Setup;
+            arg_i += 1;
+        }
+        Some("batch") => {
+            config.command = Command::Batch;
+            arg_i += 1;
+        }
+        Some("fuzz") => {
+            config.command = Command::Fuzz;
+            arg_i += 1;
+        }
+        Some("selftest") => {
+            config.command = Command::Selftest;
+            arg_i += 1;
+        }
+        Some("symmetry") => {
+            config.command = Command::Symmetry;
+            arg_i += 1;
+        }
+        Some("continue") => {
+            let path = session_path();
+            if !path.exists() {
+                println!("There is no unfinished solve to continue!");
+                process::exit(0);
+            }
+            if let Err(e) = resume_from_checkpoint(&mut config, &path) {
+                println!("Could not continue from \"{}\": {}!", path.display(), e);
+                process::exit(0);
+            }
+            // A frame export carries on where it stopped; any other solve
+            // is picked up in the terminal.
+            let checkpoint = config.resumed_checkpoint.as_deref().unwrap_or_default();
+            if checkpoint.lines().any(|line| line == "command: frames") {
+                config.command = Command::Frames;
+            }
+            arg_i += 1;
+        }
+        Some("scenario") => {
+            config.command = Command::Scenario(get_path(&args, 2, "scenario"));
+            arg_i += 2;
+        }
+        _ => {}
+    }
+    while let Some(arg) = args.get(arg_i) {
+        match &arg[..] {
+            "-H" | "--help" => {
+                display_help();
+                process::exit(0);
+            }
+            "-D" | "--delay" => {
+                config.delay = get_delay(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "-N" | "--height" => {
+                config.height = get_height(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "-L" | "--loglevel" => {
+                config.log = get_log(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "-F" | "--fast-forward" => {
+                config.fast_forward = true;
+            }
+            "-S" | "--start" => {
+                config.start = get_peg(&args, arg_i + 1, "start");
+                arg_i += 1;
+            }
+            "-G" | "--goal" => {
+                config.goal = get_peg(&args, arg_i + 1, "goal");
+                arg_i += 1;
+            }
+            "--status" => {
+                config.show_status = true;
+            }
+            "--status-format" => {
+                config.status_format = get_status_format(&args, arg_i + 1);
+                config.show_status = true;
+                arg_i += 1;
+            }
+            "--sparkline" => {
+                config.sparkline = Some(match args.get(arg_i + 1).map(|arg| arg.parse()) {
+                    Some(Ok(length)) => {
+                        arg_i += 1;
+                        length
+                    }
+                    _ => SPARKLINE_LENGTH,
+                });
+            }
+            "--progress" => {
+                config.show_progress = true;
+            }
+            "--out" if matches!(config.command, Command::Frames) => {
+                config.frames_out = Some(get_path(&args, arg_i + 1, "frame output"));
+                arg_i += 1;
+            }
+            "--format" if matches!(config.command, Command::Frames) => {
+                config.frames_format = get_frame_format(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--moves" if matches!(config.command, Command::Diff) => {
+                let (left, right) = get_pair(&args, arg_i + 1, "moves");
+                let (left, right) = match (left.parse::<usize>(), right.parse::<usize>()) {
+                    (Ok(left), Ok(right)) => (left, right),
+                    _ => {
+                        println!("Please specify two valid move indices for moves!");
+                        println!("Do -H or --help for more information.");
+                        process::exit(0);
+                    }
+                };
+                config.diff_input = Some(DiffInput::Moves(left, right));
+                arg_i += 2;
+            }
+            "--states" if matches!(config.command, Command::Diff) => {
+                let (left, right) = get_pair(&args, arg_i + 1, "states");
+                config.diff_input = Some(DiffInput::States(left, right));
+                arg_i += 2;
+            }
+            "--skip-stalled" => {
+                config.skip_stalled = true;
+            }
+            "--adaptive" => {
+                config.adaptive = true;
+            }
+            "--calibrate" => {
+                config.calibrate = true;
+            }
+            "--step-mode" => {
+                config.step_mode = get_step_mode(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--heatmap" => {
+                config.heatmap = true;
+            }
+            "--capacity" => {
+                config.capacities = get_capacities(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--freeze" => {
+                config.frozen = Some(get_frozen(&args, arg_i + 1));
+                arg_i += 1;
+            }
+            "--disk-color" => {
+                config.disk_colors.extend(get_disk_colors(&args, arg_i + 1));
+                arg_i += 1;
+            }
+            "--tween" => {
+                config.tween = get_tween(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--locale" => {
+                config.locale = get_locale(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--heights" if matches!(config.command, Command::Batch) => {
+                config.heights = get_heights(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--animate" if matches!(config.command, Command::Batch) => {
+                config.animate = true;
+            }
+            "--summary-format" => {
+                config.summary_format = get_summary_format(&args, arg_i + 1);
+                arg_i += 1;
+            }
+            "--checkpoint" => {
+                config.checkpoint = get_path(&args, arg_i + 1, "checkpoint");
+                arg_i += 1;
+            }
+            "--resume" => {
+                let path = get_path(&args, arg_i + 1, "resume");
+                if let Err(e) = resume_from_checkpoint(&mut config, Path::new(&path)) {
+                    println!("Could not resume from \"{}\": {}!", path, e);
+                    println!("Do -H or --help for more information.");
+                    process::exit(0);
+                }
+                arg_i += 1;
+            }
+            "--manifest" => {
+                config.manifest = Some(get_path(&args, arg_i + 1, "manifest"));
+                arg_i += 1;
+            }
+            "--dump-frames" => {
+                config.dump_frames = Some(get_path(&args, arg_i + 1, "frame dump"));
+                arg_i += 1;
+            }
+            _ => {
+                println!("Unknown argument \"{}\"!", args[arg_i]);
+                println!("Do -H or --help for more informatin.");
+                process::exit(0);
+            }
+        }
+        arg_i += 1;
+    }
+    if let Err(e) = config.validate() {
+        println!("{}!", e);
+        println!("Do -H or --help for more information.");
+        process::exit(0);
+    }
+
+    config
+}
+
+fn display_help() {
+    println!("Usage: hanoi [OPTION...]");
+    println!("       hanoi frames --out [dir] [--format txt|ansi] [OPTION...]");
+    println!("       hanoi diff (--moves [a] [b] | --states [a] [b]) [OPTION...]");
+    println!("       hanoi scenario [file] [OPTION...]");
+    println!("       hanoi batch --heights [range] [--animate] [OPTION...]");
+    println!("       hanoi setup [OPTION...]");
+    println!("       hanoi fuzz < [input]");
+    println!("Solves the tower of hanoi in your terminal!\n");
+    println!("Commands:");
+    println!("frames");
+    println!("\tWrites every frame of the solution to numbered files in a directory.");
+    println!("\t--out [dir] sets the directory, --format [txt|ansi] sets the file format.");
+    println!("\tDefault format of txt");
+    println!("diff");
+    println!("\tShows two states side by side with the differing cells highlighted.");
+    println!("\t--moves [a] [b] compares the solution after [a] and [b] moves.");
+    println!("\t--states [a] [b] compares two states written like \"3,2/1/\",");
+    println!("\twhere '/' separates pegs and ',' separates disks from the bottom up.");
+    println!("scenario");
+    println!("\tRuns the actions listed in [file], one per line:");
+    println!("\t\theight|delay [value], start|goal [peg], loglevel [value],");
+    println!("\t\tstatus|progress|fast-forward [on|off], pause-at [move] [ms],");
+    println!("\t\tsolve, wait [ms], quit");
+    println!("\tLines starting with # are ignored; [OPTION...] sets the starting values.");
+    println!("continue");
+    println!("\tPicks up the last solve that was interrupted, cancelled or failed to draw,");
+    println!("\tor carries on writing the frames of an interrupted frames export,");
+    println!(
+        "\tfrom a copy of its checkpoint kept in the home directory as {}.",
+        SESSION_FILE
+    );
+    println!("setup");
+    println!("\tAsks for the height, pegs and variant one screen at a time, showing a preview");
+    println!("\tof the starting position, then solves it. [OPTION...] sets the first answers.");
+    println!("batch");
+    println!("\tSolves a tower of each height in [range] and reports moves, time and speed.");
+    println!("\t[range] is like 3..=12, 3..13 or 3,5,8. Runs headless unless --animate is given.");
+    println!("symmetry");
+    println!("\tSolves the tower once, then mirrors or rotates the pegs on each m or r pressed,");
+    println!("\tshowing the relabelled solution next to solving the relabelled puzzle directly.");
+    println!("selftest");
+    println!(
+        "\tSolves every variant for towers of up to {} disks and checks each solution",
+        selftest::MAX_ORACLE_HEIGHT
+    );
+    println!("\tis legal and as short as a search of every state says it can be.");
+    println!("fuzz");
+    println!("\tTurns the bytes on stdin into a scrambled tower and a sequence of moves,");
+    println!("\tpanicking if the tower ever reaches an impossible state. Meant for fuzzers.\n");
+    println!("Arguments:");
+    println!("-H, --help");
+    println!("\t Displays help");
+    println!("-D [value], --delay [value]");
+    println!("\tSets the delay between peg moves; [value] is a positive integer in milliseconds.");
+    println!("\tDefault value of 100");
+    println!("-N [value], --height [value]");
+    println!("\tSets the height of the tower; [value] is a positive integer.");
+    println!("-L [value], --loglevel [value]");
+    println!("\tSets the loglevel for the program (not capital sensitive).");
+    println!("\tPossible values are:");
+    println!("\t\t[None] - print nothing");
+    println!("\t\t[Minimal] - only print moves taken");
+    println!("\t\t[All] - print both moves taken, tower height and print delay");
+    println!("\tDefault value of [Minimal]");
+    println!("-S [peg], --start [peg]");
+    println!(
+        "\tSets the peg the tower starts on; [peg] is a letter (A, B, C) or number (1, 2, 3)."
+    );
+    println!("\tDefault value of A");
+    println!("-G [peg], --goal [peg]");
+    println!(
+        "\tSets the peg the tower is moved to; [peg] is a letter (A, B, C) or number (1, 2, 3)."
+    );
+    println!("\tDefault value of C");
+    println!("--capacity [peg]=[value],...");
+    println!("\tLimits how many disks a peg can hold, e.g. B=2; the solution is then found by");
+    println!(
+        "\tsearch, which supports towers of at most {} disks.",
+        MAX_SEARCH_HEIGHT
+    );
+    println!("--freeze [disk]:[moves]");
+    println!("\tKeeps [disk] (1 is the smallest) from moving until [moves] moves have been made.");
+    println!("\tThe frozen disk is drawn dimmed; the solution is found by search if needed.");
+    println!("--disk-color [disk]=[color],...");
+    println!("\tDraws a disk size (1 is the smallest) in a color, e.g. 1=red,2=#00ff00.");
+    println!("\tColors are names such as red or dark_blue, or #rrggbb; text frames stay plain.");
+    println!("--tween [lift],[slide],[drop]");
+    println!("\tAnimates each move with up to that many in-between frames per phase, e.g.");
+    println!("\t3,4,3, leaving out frames that would not move the disk. Each phase can have");
+    println!("\tat most {} frames.", MAX_PHASE_FRAMES);
+    println!("\tThe lift slows down, the drop speeds up and the delay is shared between frames.");
+    println!("--locale [value]");
+    println!("\tSets how numbers and durations are formatted, e.g. en, de or fr.");
+    println!("\tDefaults to the LC_ALL, LC_NUMERIC or LANG environment variable");
+    println!("--summary-format [text|json]");
+    println!("\tSets how the report at the end of a run is written. With json the tower is");
+    println!("\tnot drawn, so the report is the only thing written to standard output.");
+    println!("\tDefault value of text");
+    println!("--step-mode [auto|manual]");
+    println!("\tIn manual mode, makes one move per keypress from the very first move;");
+    println!("\tq, Esc or Ctrl-C stops the solve and saves a checkpoint.");
+    println!("\tDefault value of auto");
+    println!("-F, --fast-forward");
+    println!("\tRenders every frame without sleeping between moves.");
+    println!("--status");
+    println!("\tShows which sub-tower is currently being moved above the tower.");
+    println!("--status-format [template]");
+    println!(
+        "\tShows the status bar laid out like [template], e.g. \"{{move}}/{{total}} {{elapsed}}\"."
+    );
+    println!(
+        "\tFields are {}.",
+        StatusField::names()
+            .iter()
+            .map(|name| format!("{{{}}}", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("\tDefault value of {{action}}");
+    println!("--sparkline [length]");
+    println!("\tShows a bar for each of the last [length] moves, taller for larger disks.");
+    println!(
+        "\t[length] is optional, default value of {}",
+        SPARKLINE_LENGTH
+    );
+    println!("--progress");
+    println!("\tShows how many disks are already in their final place on the goal peg.");
+    println!("--skip-stalled");
+    println!("\tSkips drawing frames to catch up after a frame takes far longer than the delay.");
+    println!("\tA warning is always printed to stderr when a frame stalls.");
+    println!("--calibrate");
+    println!("\tDraws a few frames first to time the terminal, then adds as many in-between");
+    println!("\tframes as fit in the delay unless --tween is given, and turns on --skip-stalled");
+    println!("\tand --adaptive if the terminal is too slow for the delay.");
+    println!("--heatmap");
+    println!("\tAfter the solve, shows how often each peg and level held a disk, from dark");
+    println!("\tblue for never to red for always. Added to the summary with json.");
+    println!("--adaptive");
+    println!("\tDraws less while the terminal cannot keep up: first without disk colors, then");
+    println!("\twithout in-between frames, then skipping frames. Recovers once it catches up.");
+    println!("--checkpoint [file]");
+    println!("\tSets where progress is saved if drawing fails partway through a solve.");
+    println!("\tDefault value of {}", CHECKPOINT_FILE);
+    println!("--resume [file]");
+    println!("\tContinues a solve from a checkpoint with the settings it was started with.");
+    println!("--manifest [file]");
+    println!("\tWrites every effective setting and what was detected about the terminal to");
+    println!("\t[file] at the end of the run. Giving the same command --resume [file]");
+    println!("\treruns it with the same settings. Runs that stop early write it too;");
+    println!("\tdiff, fuzz, selftest and symmetry do not write one.");
+    println!("--dump-frames [file]");
+    println!("\tWrites every rendered frame to [file].");
+}
+
+fn get_delay(args: &[String], index: usize) -> u32 {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for delay!");
+            println!("Do -H or --help for more informatin.");
+            process::exit(0);
+        }
+        Some(string) => match string.parse::<u32>() {
+            Ok(val) => val,
+            Err(_) => {
+                println!("Please specify a valid value for delay!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_height(args: &[String], index: usize) -> u32 {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for height!");
+            println!("Do -H or --help for more informatin.");
+            process::exit(0);
+        }
+        Some(string) => match string.parse::<u32>() {
+            Ok(val) => val,
+            Err(_) => {
+                println!("{} is not a valid value for height!", string);
+                println!("Please specify a valid value for height!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_log(args: &[String], index: usize) -> LogLevel {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for log level!");
             println!("Do -H or --help for more information.");
             process::exit(0);
         }
-        Some(string) => match &string.to_lowercase()[..] {
-            "all" => LogLevel::All,
-            "minimal" => LogLevel::Minimal,
-            "none" => LogLevel::None,
-            _ => {
+        Some(string) => match LogLevel::parse(string) {
+            Some(log) => log,
+            None => {
                 println!("{} is not a valid value for log level!", string);
                 println!("Please specify a valid value for log level!");
                 println!("Do -H or --help for more information.");
@@ -260,3 +2441,583 @@ fn get_log(args: &Vec<String>, index: usize) -> LogLevel {
         },
     }
 }
+
+fn get_step_mode(args: &[String], index: usize) -> StepMode {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for step mode!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match StepMode::parse(string) {
+            Some(step_mode) => step_mode,
+            None => {
+                println!("{} is not a valid value for step mode!", string);
+                println!("Please specify auto or manual!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_path(args: &[String], index: usize, name: &str) -> String {
+    match args.get(index) {
+        None => {
+            println!("Please specify a path for {}!", name);
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string.to_owned(),
+    }
+}
+
+fn get_peg(args: &[String], index: usize, name: &str) -> Peg {
+    match args.get(index) {
+        None => {
+            println!("Please specify a peg for {}!", name);
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match Peg::parse_within(string, PEG_COUNT) {
+            Ok(peg) => peg,
+            Err(e) => {
+                println!("{} is not a valid value for {}: {}!", string, name, e);
+                println!("Please specify a valid peg for {}!", name);
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_frame_format(args: &[String], index: usize) -> FrameFormat {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for frame format!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match string.parse::<FrameFormat>() {
+            Ok(format) => format,
+            Err(_) => {
+                println!("{} is not a valid value for frame format!", string);
+                println!("Please specify a valid value for frame format!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_status_format(args: &[String], index: usize) -> StatusTemplate {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for status format!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match string.parse::<StatusTemplate>() {
+            Ok(status_format) => status_format,
+            Err(e) => {
+                println!("{} is not a valid value for status format: {}!", string, e);
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_pair(args: &[String], index: usize, name: &str) -> (String, String) {
+    match (args.get(index), args.get(index + 1)) {
+        (Some(left), Some(right)) => (left.to_owned(), right.to_owned()),
+        _ => {
+            println!("Please specify two values for {}!", name);
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+fn get_locale(args: &[String], index: usize) -> Locale {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for locale!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match Locale::from_tag(string) {
+            Some(locale) => locale,
+            None => {
+                println!("{} is not a supported locale!", string);
+                println!("Please specify a valid value for locale!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+fn get_capacities(args: &[String], index: usize) -> [Option<u32>; PEG_COUNT] {
+    let string = match args.get(index) {
+        None => {
+            println!("Please specify a value for capacity!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string,
+    };
+    let mut capacities = [None; PEG_COUNT];
+    for limit in string.split(',') {
+        match parse_capacity(limit) {
+            Some((peg, capacity)) => capacities[peg.index()] = Some(capacity),
+            None => {
+                println!("{} is not a valid value for capacity!", limit);
+                println!("Please specify capacities like B=2!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        }
+    }
+    capacities
+}
+
+/// Parses a single capacity limit such as `B=2`.
+fn parse_capacity(string: &str) -> Option<(Peg, u32)> {
+    let (peg, capacity) = string.split_once('=')?;
+    let peg = Peg::parse_within(peg, PEG_COUNT).ok()?;
+    Some((peg, capacity.parse().ok()?))
+}
+
+/// Parses a single disk color such as `1=red` or `2=#00ff00`.
+fn parse_disk_color(string: &str) -> Option<(u32, Color)> {
+    let (disk, color) = string.split_once('=')?;
+    let disk = disk.parse().ok().filter(|disk| *disk > 0)?;
+    let color = match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 => {
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            Color::from((channel(0)?, channel(2)?, channel(4)?))
+        }
+        Some(_) => return None,
+        None => Color::try_from(color).ok()?,
+    };
+    Some((disk, color))
+}
+
+/// Writes a color the way [`parse_disk_color`] reads it.
+fn format_color(color: Color) -> String {
+    let name = match color {
+        Color::Rgb { r, g, b } => return format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Black => "black",
+        Color::DarkGrey => "dark_grey",
+        Color::Red => "red",
+        Color::DarkRed => "dark_red",
+        Color::Green => "green",
+        Color::DarkGreen => "dark_green",
+        Color::Yellow => "yellow",
+        Color::DarkYellow => "dark_yellow",
+        Color::Blue => "blue",
+        Color::DarkBlue => "dark_blue",
+        Color::Magenta => "magenta",
+        Color::DarkMagenta => "dark_magenta",
+        Color::Cyan => "cyan",
+        Color::DarkCyan => "dark_cyan",
+        Color::White => "white",
+        Color::Grey => "grey",
+        Color::Reset | Color::AnsiValue(_) => "reset",
+    };
+    name.to_string()
+}
+
+/// Parses a freeze such as `3:5`.
+fn parse_frozen(string: &str) -> Option<Frozen> {
+    let (disk, moves) = string.split_once(':')?;
+    Some(Frozen {
+        disk: disk.parse().ok()?,
+        moves: moves.parse().ok()?,
+    })
+}
+
+/// Parses tween frame counts such as `3,4,3`.
+fn parse_tween(string: &str) -> Option<Tween> {
+    let frames: Vec<Result<u32, _>> = string.split(',').map(|part| part.parse::<u32>()).collect();
+    match &frames[..] {
+        [Ok(lift), Ok(slide), Ok(drop)]
+            if [lift, slide, drop]
+                .iter()
+                .all(|&&frames| frames <= MAX_PHASE_FRAMES) =>
+        {
+            Some(Tween {
+                lift: *lift,
+                slide: *slide,
+                drop: *drop,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn get_disk_colors(args: &[String], index: usize) -> Vec<(u32, Color)> {
+    let string = match args.get(index) {
+        None => {
+            println!("Please specify a value for disk color!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string,
+    };
+    let mut disk_colors = Vec::new();
+    for assignment in string.split(',') {
+        match parse_disk_color(assignment) {
+            Some(disk_color) => disk_colors.push(disk_color),
+            None => {
+                println!("{} is not a valid value for disk color!", assignment);
+                println!("Please specify disk colors like 1=red or 2=#00ff00!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        }
+    }
+    disk_colors
+}
+
+fn get_frozen(args: &[String], index: usize) -> Frozen {
+    let string = match args.get(index) {
+        None => {
+            println!("Please specify a value for freeze!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string,
+    };
+    match parse_frozen(string) {
+        Some(frozen) => frozen,
+        None => {
+            println!("{} is not a valid value for freeze!", string);
+            println!("Please specify a freeze like 3:5!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+fn get_tween(args: &[String], index: usize) -> Tween {
+    let string = match args.get(index) {
+        None => {
+            println!("Please specify a value for tween!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string,
+    };
+    match parse_tween(string) {
+        Some(tween) => tween,
+        None => {
+            println!("{} is not a valid value for tween!", string);
+            println!(
+                "Please specify three frame counts up to {} like 3,4,3!",
+                MAX_PHASE_FRAMES
+            );
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+fn get_heights(args: &[String], index: usize) -> Vec<u32> {
+    let string = match args.get(index) {
+        None => {
+            println!("Please specify a value for heights!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => string,
+    };
+    match parse_heights(string) {
+        Some(heights) => heights,
+        None => {
+            println!("{} is not a valid value for heights!", string);
+            println!("Please specify heights like 3..=12 or 3,5,8!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+/// Parses heights written like 3..=12, 3..13 or 3,5,8, as long as there is at least one.
+fn parse_heights(string: &str) -> Option<Vec<u32>> {
+    let heights: Vec<u32> = if let Some((first, last)) = string.split_once("..=") {
+        (first.parse().ok()?..=last.parse().ok()?).collect()
+    } else if let Some((first, end)) = string.split_once("..") {
+        (first.parse().ok()?..end.parse().ok()?).collect()
+    } else {
+        string
+            .split(',')
+            .map(|height| height.parse().ok())
+            .collect::<Option<_>>()?
+    };
+    Some(heights).filter(|heights| !heights.is_empty())
+}
+
+fn get_summary_format(args: &[String], index: usize) -> SummaryFormat {
+    match args.get(index) {
+        None => {
+            println!("Please specify a value for summary format!");
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+        Some(string) => match SummaryFormat::parse(string) {
+            Some(summary_format) => summary_format,
+            None => {
+                println!("{} is not a valid value for summary format!", string);
+                println!("Please specify a valid value for summary format!");
+                println!("Do -H or --help for more information.");
+                process::exit(0);
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(args: &[&str]) -> Config {
+        let args = std::iter::once("hanoi")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect();
+        get_parameters(args)
+    }
+
+    #[test]
+    fn watchdog_skips_frames_after_a_stall() {
+        let delay = Duration::from_millis(10);
+        let mut watchdog = Watchdog::new(true);
+        assert!(!watchdog.check(Duration::from_millis(200), delay));
+        assert!(watchdog.check(Duration::from_millis(1010), delay));
+        assert_eq!(watchdog.stalls, 1);
+        let skipped = (0..200).take_while(|_| watchdog.should_skip(delay)).count();
+        assert_eq!(skipped, 100);
+        assert!(!Watchdog::new(false).should_skip(delay));
+    }
+
+    #[test]
+    fn fidelity_steps_down_and_back_up() {
+        assert!(Fidelity::Full.lower() == Fidelity::Uncolored);
+        assert!(Fidelity::Untweened.lower() == Fidelity::Skipping);
+        assert!(Fidelity::Skipping.lower() == Fidelity::Skipping);
+        assert!(Fidelity::Skipping.higher() == Fidelity::Untweened);
+        assert!(Fidelity::Uncolored.higher() == Fidelity::Full);
+        assert!(Fidelity::Full.higher() == Fidelity::Full);
+    }
+
+    #[test]
+    fn watchdog_adapts_fidelity_to_the_budget() {
+        let budget = Duration::from_millis(20);
+        let mut watchdog = Watchdog::new(false);
+        watchdog.adaptive = true;
+        for _ in 0..ADAPT_FRAMES * 10 {
+            watchdog.adapt(Duration::from_millis(200), budget);
+        }
+        assert!(watchdog.fidelity == Fidelity::Skipping);
+        assert_eq!(watchdog.downgrades, 3);
+        for _ in 0..ADAPT_FRAMES * 40 {
+            watchdog.adapt(Duration::from_millis(0), budget);
+        }
+        assert!(watchdog.fidelity == Fidelity::Full);
+        assert_eq!(watchdog.downgrades, 3);
+    }
+
+    #[test]
+    fn watchdog_holds_fidelity_unless_adaptive() {
+        let mut watchdog = Watchdog::new(false);
+        for _ in 0..ADAPT_FRAMES * 10 {
+            watchdog.adapt(Duration::from_millis(200), Duration::from_millis(20));
+        }
+        assert!(watchdog.fidelity == Fidelity::Full);
+    }
+
+    #[test]
+    fn transit_leaves_out_repeated_and_resting_frames() {
+        let mut config = config(&["-N", "3", "--tween", "8,8,8"]);
+        config.summary_format = SummaryFormat::Json;
+        let tower = configured_tower(&config, 3, Clock::new(true));
+        let transit = tower.get_transit(Peg::new(0), Peg::new(2));
+        assert!(!transit.is_empty() && transit.len() < 24);
+        assert!(transit.windows(2).all(|pair| pair[0] != pair[1]));
+        let from_rest = (2, 6);
+        let to_rest = (0, 30);
+        assert!(transit.iter().all(|floating| {
+            let position = (floating.row, floating.center);
+            position != from_rest && position != to_rest
+        }));
+        assert_eq!((transit[0].row, transit[0].center), (3, 6));
+        let last = transit.last().unwrap();
+        assert_eq!(last.center, 30);
+    }
+
+    #[test]
+    fn lists_only_the_first_moves_of_the_solution() {
+        let (a, b, c) = (Peg::new(0), Peg::new(1), Peg::new(2));
+        let all = solution_moves(3, a, c, usize::MAX);
+        assert_eq!(
+            all,
+            [(a, c), (a, b), (c, b), (a, c), (b, a), (b, c), (a, c)]
+        );
+        for count in 0..=all.len() {
+            assert_eq!(solution_moves(3, a, c, count), all[..count]);
+        }
+        assert_eq!(solution_moves(60, a, c, 2), [(a, b), (a, c)]);
+    }
+
+    #[test]
+    fn parses_states() {
+        assert_eq!(
+            Tower::parse_state("3,2/1/").unwrap(),
+            [vec![3, 2], vec![1], vec![]]
+        );
+        assert_eq!(
+            Tower::parse_state(" 1 //").unwrap(),
+            [vec![1], vec![], vec![]]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_states() {
+        for invalid in ["3,2,1", "3,2/1//", "1,2//", "3,1//", "0//", "x//"].iter() {
+            assert!(Tower::parse_state(invalid).is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn batches_validate_every_height() {
+        let mut batch = config(&["batch", "--heights", "7..=8"]);
+        batch.frozen = Some(Frozen { disk: 7, moves: 5 });
+        assert!(batch.validate().is_ok());
+        batch.heights = vec![6, 7];
+        assert!(batch.validate().is_err());
+        batch.frozen = None;
+        batch.capacities[2] = Some(7);
+        assert!(batch.validate().is_ok());
+        batch.heights.push(8);
+        assert!(batch.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_freezes_too_long_to_search() {
+        let mut solve = config(&["-N", "3"]);
+        solve.frozen = Some(Frozen {
+            disk: 2,
+            moves: u64::MAX,
+        });
+        assert!(solve.validate().is_err());
+        solve.frozen = Some(Frozen {
+            disk: 2,
+            moves: MAX_SEARCH_STATES as u64,
+        });
+        assert!(solve.validate().is_ok());
+    }
+
+    #[test]
+    fn parses_heights() {
+        assert_eq!(parse_heights("3..=5"), Some(vec![3, 4, 5]));
+        assert_eq!(parse_heights("3..5"), Some(vec![3, 4]));
+        assert_eq!(parse_heights("8,3"), Some(vec![8, 3]));
+        assert_eq!(parse_heights("5..5"), None);
+        assert_eq!(parse_heights("3,x"), None);
+    }
+
+    #[test]
+    fn summary_compares_with_the_optimum_under_the_rules() {
+        let mut config = config(&["-N", "5", "--freeze", "2:40", "-D", "0"]);
+        config.summary_format = SummaryFormat::Json;
+        let mut tower = configured_tower(&config, 5, Clock::new(true));
+        tower.set_headless();
+        assert!(tower.solve().is_ok());
+        let summary = summary_json(&config, &tower).to_string();
+        let moves = format!(
+            "\"moves\":{},\"optimal_moves\":{},",
+            tower.moves, tower.moves
+        );
+        assert!(tower.moves > solver::optimal_moves(5));
+        assert!(summary.contains(&moves));
+    }
+
+    #[test]
+    fn elapsed_time_starts_with_the_first_move() {
+        let mut tower = Tower::new(3, 0, Peg::new(0), Peg::new(2), Clock::new(true));
+        tower.set_headless();
+        let set_up = Duration::from_millis(50);
+        std::thread::sleep(set_up);
+        assert!(tower.solve().is_ok());
+        assert!(tower.started.elapsed() < set_up);
+    }
+
+    #[test]
+    fn recording_header_reads_back() {
+        let recorded = config(&[
+            "frames",
+            "-N",
+            "4",
+            "-S",
+            "B",
+            "--capacity",
+            "A=3",
+            "--freeze",
+            "2:1",
+            "--tween",
+            "1,2,3",
+            "--disk-color",
+            "1=red",
+            "--status-format",
+            "{move}/{total}",
+            "--format",
+            "ansi",
+        ]);
+        let header = recording_header(&recorded, recorded.height);
+        let mut restored = config(&["frames"]);
+        assert_eq!(apply_recording_header(&mut restored, &header), Ok(0));
+        assert_eq!(recording_header(&restored, restored.height), header);
+    }
+
+    #[test]
+    fn manifest_reads_back() {
+        let recorded = config(&[
+            "-N",
+            "5",
+            "-L",
+            "none",
+            "-F",
+            "--summary-format",
+            "json",
+            "--step-mode",
+            "manual",
+            "--heatmap",
+            "--locale",
+            "de",
+        ]);
+        let tower = Tower::new(5, 0, recorded.start, recorded.goal, Clock::new(true));
+        let mut restored = config(&[]);
+        assert_eq!(
+            apply_recording_header(&mut restored, &manifest(&recorded, &tower)),
+            Ok(0)
+        );
+        assert!(matches!(restored.log, LogLevel::None));
+        assert!(restored.summary_format == SummaryFormat::Json);
+        assert!(restored.step_mode == StepMode::Manual);
+        assert!(restored.fast_forward && restored.heatmap);
+        assert_eq!(restored.locale.tag(), "de");
+    }
+
+    #[test]
+    fn only_runs_that_write_a_manifest_take_one() {
+        let mut diff = config(&["diff"]);
+        assert!(diff.validate().is_ok());
+        diff.manifest = Some("manifest.txt".to_string());
+        assert!(diff.validate().is_err());
+        let mut batch = config(&["batch", "--heights", "3"]);
+        batch.manifest = Some("manifest.txt".to_string());
+        assert!(batch.validate().is_ok());
+    }
+}
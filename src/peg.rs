@@ -0,0 +1,115 @@
+use std::{fmt, str::FromStr};
+
+/// A peg on the board, identified by its zero-based position from the left.
+///
+/// Pegs are named with letters (`A`, `B`, ..., `Z`, `AA`, ...) and can be
+/// parsed either from that name or from their one-based number (`"2"`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Peg(usize);
+
+impl Peg {
+    pub fn new(index: usize) -> Self {
+        Peg(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+
+    pub fn name(&self) -> String {
+        let mut name = Vec::new();
+        let mut n = self.0 + 1;
+        while n > 0 {
+            n -= 1;
+            name.push(b'A' + (n % 26) as u8);
+            n /= 26;
+        }
+        name.reverse();
+        String::from_utf8(name).unwrap()
+    }
+
+    /// Parses a peg the way [`FromStr`] does, accepting only the first `count`.
+    pub fn parse_within(s: &str, count: usize) -> Result<Peg, String> {
+        match s.parse::<Peg>()? {
+            peg if peg.index() < count => Ok(peg),
+            peg => Err(format!(
+                "there is no peg {}, only {} pegs exist",
+                peg, count
+            )),
+        }
+    }
+
+    /// Iterates over the first `count` pegs, left to right.
+    pub fn all(count: usize) -> impl Iterator<Item = Peg> {
+        (0..count).map(Peg)
+    }
+
+    /// Returns the one peg out of three that is neither `a` nor `b`.
+    pub fn other(a: Peg, b: Peg) -> Peg {
+        Peg(3 - a.0 - b.0)
+    }
+}
+
+impl fmt::Display for Peg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Peg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(number) = s.parse::<usize>() {
+            return match number {
+                0 => Err("peg numbers start at 1".to_string()),
+                _ => Ok(Peg(number - 1)),
+            };
+        }
+        if s.is_empty() || !s.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("\"{}\" is not a peg name or number", s));
+        }
+        let mut index = 0usize;
+        for c in s.to_ascii_uppercase().bytes() {
+            index = index
+                .checked_mul(26)
+                .and_then(|i| i.checked_add((c - b'A') as usize + 1))
+                .ok_or_else(|| format!("\"{}\" is too large a peg name", s))?;
+        }
+        Ok(Peg(index - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_parse_back() {
+        for index in 0..1000 {
+            assert_eq!(Peg(index).name().parse::<Peg>(), Ok(Peg(index)));
+        }
+        assert_eq!(Peg(26).name(), "AA");
+    }
+
+    #[test]
+    fn parses_numbers_and_lowercase_names() {
+        assert_eq!("2".parse::<Peg>(), Ok(Peg(1)));
+        assert_eq!("c".parse::<Peg>(), Ok(Peg(2)));
+    }
+
+    #[test]
+    fn parses_only_pegs_within_the_count() {
+        assert_eq!(Peg::parse_within("C", 3), Ok(Peg(2)));
+        assert!(Peg::parse_within("D", 3).is_err());
+        assert!(Peg::parse_within("4", 3).is_err());
+        assert!(Peg::parse_within("", 3).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_pegs() {
+        for invalid in ["", "0", "A1", "-1", &"Z".repeat(20)].iter() {
+            assert!(invalid.parse::<Peg>().is_err(), "{} parsed", invalid);
+        }
+    }
+}
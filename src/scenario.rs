@@ -0,0 +1,188 @@
+use std::{fs, process, thread, time::Duration};
+
+use crate::{build_tower, print_summary, solve_or_exit, Config, LogLevel, Peg, PEG_COUNT};
+
+/// One line of a scenario file.
+enum Action {
+    Height(u32),
+    Delay(u32),
+    Start(Peg),
+    Goal(Peg),
+    Log(LogLevel),
+    Status(bool),
+    Progress(bool),
+    FastForward(bool),
+    PauseAt(u64, Duration),
+    Solve,
+    Wait(Duration),
+    Quit,
+}
+
+/// Reads and runs a scenario file, exiting with a message if it is invalid.
+pub fn run_file(path: &str, config: &mut Config) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            println!("Could not read scenario file \"{}\": {}", path, e);
+            process::exit(0);
+        }
+    };
+    match parse(&contents) {
+        Ok(actions) => run(actions, config),
+        Err(e) => {
+            println!("{}: {}!", path, e);
+            println!("Do -H or --help for more information.");
+            process::exit(0);
+        }
+    }
+}
+
+/// Parses every line up front so a mistake is reported before anything runs.
+fn parse(contents: &str) -> Result<Vec<Action>, String> {
+    let mut actions = Vec::new();
+    for (line_i, line) in contents.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() || words[0].starts_with('#') {
+            continue;
+        }
+        let action = parse_action(&words).map_err(|e| format!("line {}: {}", line_i + 1, e))?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+fn parse_action(words: &[&str]) -> Result<Action, String> {
+    let expected_values = match words[0] {
+        "solve" | "quit" => 0,
+        "pause-at" => 2,
+        _ => 1,
+    };
+    if words.len() != expected_values + 1 {
+        return Err(format!("{} takes {} value(s)", words[0], expected_values));
+    }
+    let action = match words[0] {
+        "height" => Action::Height(parse_number(words[1])?),
+        "delay" => Action::Delay(parse_number(words[1])?),
+        "start" => Action::Start(Peg::parse_within(words[1], PEG_COUNT)?),
+        "goal" => Action::Goal(Peg::parse_within(words[1], PEG_COUNT)?),
+        "loglevel" => match LogLevel::parse(words[1]) {
+            Some(log) => Action::Log(log),
+            None => return Err(format!("{} is not a valid log level", words[1])),
+        },
+        "status" => Action::Status(parse_switch(words[1])?),
+        "progress" => Action::Progress(parse_switch(words[1])?),
+        "fast-forward" => Action::FastForward(parse_switch(words[1])?),
+        "pause-at" => Action::PauseAt(
+            parse_number(words[1])?,
+            Duration::from_millis(parse_number(words[2])?),
+        ),
+        "solve" => Action::Solve,
+        "wait" => Action::Wait(Duration::from_millis(parse_number(words[1])?)),
+        "quit" => Action::Quit,
+        other => return Err(format!("unknown action \"{}\"", other)),
+    };
+    Ok(action)
+}
+
+fn parse_number<T: std::str::FromStr>(word: &str) -> Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("{} is not a valid positive integer", word))
+}
+
+fn parse_switch(word: &str) -> Result<bool, String> {
+    match word {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(format!("{} is not on or off", word)),
+    }
+}
+
+fn run(actions: Vec<Action>, config: &mut Config) {
+    let mut pauses = Vec::new();
+    for action in actions {
+        match action {
+            Action::Height(height) => config.height = height,
+            Action::Delay(delay) => config.delay = delay,
+            Action::Start(peg) => config.start = peg,
+            Action::Goal(peg) => config.goal = peg,
+            Action::Log(log) => config.log = log,
+            Action::Status(show) => config.show_status = show,
+            Action::Progress(show) => config.show_progress = show,
+            Action::FastForward(fast_forward) => config.fast_forward = fast_forward,
+            Action::PauseAt(moves, duration) => pauses.push((moves, duration)),
+            Action::Solve => {
+                if let Err(e) = config.validate() {
+                    println!("{}!", e);
+                    process::exit(0);
+                }
+                let mut tower = build_tower(config, config.height);
+                for (moves, duration) in pauses.drain(..) {
+                    tower.pause_at(moves, duration);
+                }
+                solve_or_exit(config, &mut tower);
+                print_summary(config, &tower);
+            }
+            Action::Wait(duration) => {
+                if !config.fast_forward {
+                    thread::sleep(duration);
+                }
+            }
+            Action::Quit => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(line: &str) -> Result<Action, String> {
+        parse_action(&line.split_whitespace().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_actions() {
+        assert!(matches!(action("height 4"), Ok(Action::Height(4))));
+        assert!(matches!(action("goal b"), Ok(Action::Goal(peg)) if peg == Peg::new(1)));
+        assert!(matches!(
+            action("loglevel none"),
+            Ok(Action::Log(LogLevel::None))
+        ));
+        assert!(matches!(
+            action("fast-forward on"),
+            Ok(Action::FastForward(true))
+        ));
+        assert!(matches!(
+            action("pause-at 3 250"),
+            Ok(Action::PauseAt(3, duration)) if duration == Duration::from_millis(250)
+        ));
+        assert!(matches!(action("solve"), Ok(Action::Solve)));
+    }
+
+    #[test]
+    fn rejects_invalid_actions() {
+        for invalid in [
+            "height",
+            "height -1",
+            "start D",
+            "status maybe",
+            "pause-at 3",
+            "solve now",
+            "jump 3",
+        ]
+        .iter()
+        {
+            assert!(action(invalid).is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let actions = parse("# setup\n\nheight 3\n  # indented\nsolve\n").unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(
+            parse("height 3\nfly 3\n").err().unwrap(),
+            "line 2: unknown action \"fly\""
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    solver::{Frozen, Rules, MAX_SEARCH_HEIGHT},
+    Clock, Config, LogLevel, Peg, SolveError, Tower, PEG_COUNT,
+};
+
+/// The tallest tower checked against the oracle, which visits every state.
+pub const MAX_ORACLE_HEIGHT: u32 = 6;
+
+/// A set of rules the solver supports, built for a tower height and the
+/// auxiliary peg, the one that is neither the start nor the goal.
+struct Variant {
+    name: &'static str,
+    rules: fn(u32, Peg) -> Rules,
+}
+
+fn limit(aux: Peg, capacity: u32) -> [Option<u32>; PEG_COUNT] {
+    let mut capacities = [None; PEG_COUNT];
+    capacities[aux.index()] = Some(capacity);
+    capacities
+}
+
+const VARIANTS: [Variant; 6] = [
+    Variant {
+        name: "classic",
+        rules: |_, _| Rules::classic(),
+    },
+    Variant {
+        name: "roomy auxiliary peg",
+        rules: |height, aux| Rules {
+            capacities: limit(aux, height - 1),
+            frozen: None,
+        },
+    },
+    Variant {
+        name: "cramped auxiliary peg",
+        rules: |height, aux| Rules {
+            capacities: limit(aux, height.saturating_sub(2)),
+            frozen: None,
+        },
+    },
+    Variant {
+        name: "frozen second disk",
+        rules: |_, _| Rules {
+            capacities: [None; PEG_COUNT],
+            frozen: Some(Frozen { disk: 2, moves: 3 }),
+        },
+    },
+    Variant {
+        name: "frozen largest disk",
+        rules: |height, _| Rules {
+            capacities: [None; PEG_COUNT],
+            frozen: Some(Frozen {
+                disk: height,
+                moves: 2u64.pow(height - 1),
+            }),
+        },
+    },
+    Variant {
+        name: "roomy auxiliary peg and freeze",
+        rules: |height, aux| Rules {
+            capacities: limit(aux, height - 1),
+            frozen: Some(Frozen {
+                disk: height.min(2),
+                moves: 2,
+            }),
+        },
+    },
+];
+
+/// Solves every variant for every small height and pair of pegs, checking
+/// each solution is legal and as short as the oracle says it can be.
+/// Exits with status 1 if any case fails.
+pub fn run(config: &Config) {
+    let mut failures = 0;
+    for variant in VARIANTS.iter() {
+        let mut cases = 0;
+        for height in 1..=MAX_ORACLE_HEIGHT.min(MAX_SEARCH_HEIGHT) {
+            for start in Peg::all(PEG_COUNT) {
+                for goal in Peg::all(PEG_COUNT).filter(|goal| *goal != start) {
+                    cases += 1;
+                    if let Err(e) = check_case(variant, height, start, goal) {
+                        failures += 1;
+                        println!(
+                            "{}: height {} from {} to {}: {}",
+                            variant.name, height, start, goal, e
+                        );
+                    }
+                }
+            }
+        }
+        if let LogLevel::All = config.log {
+            println!("Checked {} cases of {}", cases, variant.name);
+        }
+    }
+    match (failures, &config.log) {
+        (0, LogLevel::None) => {}
+        (0, _) => println!("All variants match the oracle"),
+        (failures, _) => {
+            println!("{} cases did not match the oracle", failures);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn check_case(variant: &Variant, height: u32, start: Peg, goal: Peg) -> Result<(), String> {
+    let aux = Peg::other(start, goal);
+    let rules = (variant.rules)(height, aux);
+    let mut tower = Tower::new(height, 0, start, goal, Clock::new(true));
+    let start_state = tower.state.clone();
+    let shortest = shortest_solution(&start_state, goal, &rules);
+    tower.set_rules((variant.rules)(height, aux));
+    tower.set_headless();
+    tower.record_history();
+
+    match (tower.solve(), shortest) {
+        (Ok(()), Some(shortest)) => {
+            let moves = tower.history.as_deref().unwrap_or_default();
+            check_legal(&start_state, &rules, moves)?;
+            if tower.state[goal.index()].len() as u32 != height {
+                return Err("the solution does not end on the goal peg".to_string());
+            }
+            if moves.len() as u64 != shortest {
+                return Err(format!(
+                    "the solution takes {} moves but {} are enough",
+                    moves.len(),
+                    shortest
+                ));
+            }
+            Ok(())
+        }
+        (Ok(()), None) => Err("the oracle found no solution, but one was made".to_string()),
+        (Err(SolveError::Unsolvable(_)), None) => Ok(()),
+        (Err(SolveError::Unsolvable(e)), Some(shortest)) => Err(format!(
+            "the solver gave up ({}) but {} moves are enough",
+            e, shortest
+        )),
+        (Err(SolveError::Output(e)), _) => Err(format!("the solve failed: {}", e)),
+        (Err(SolveError::Cancelled), _) => Err("the solve was cancelled".to_string()),
+    }
+}
+
+fn check_legal(
+    start: &[Vec<u32>; PEG_COUNT],
+    rules: &Rules,
+    moves: &[(Peg, Peg)],
+) -> Result<(), String> {
+    let mut state = start.clone();
+    for (moves_made, (from, to)) in moves.iter().enumerate() {
+        if !is_legal(&state, rules, moves_made as u64, *from, *to) {
+            return Err(format!(
+                "move {} from {} to {} is illegal",
+                moves_made + 1,
+                from,
+                to
+            ));
+        }
+        let disk = state[from.index()].pop().unwrap();
+        state[to.index()].push(disk);
+    }
+    Ok(())
+}
+
+/// Whether moving the top disk of `from` onto `to` is legal after `moves` moves,
+/// checked from the rules' fields rather than with [`Rules::allows`], so that
+/// a mistake there is caught rather than shared.
+fn is_legal(state: &[Vec<u32>; PEG_COUNT], rules: &Rules, moves: u64, from: Peg, to: Peg) -> bool {
+    let (disk, target) = match (state[from.index()].last(), &state[to.index()]) {
+        (Some(disk), target) if from != to => (*disk, target),
+        _ => return false,
+    };
+    let onto_larger = target.last().is_none_or(|top| *top > disk);
+    let thawed = rules
+        .frozen
+        .is_none_or(|frozen| frozen.disk != disk || moves >= frozen.moves);
+    let has_room =
+        rules.capacities[to.index()].is_none_or(|capacity| target.len() < capacity as usize);
+    onto_larger && thawed && has_room
+}
+
+/// Counts the moves in a shortest solution by searching every reachable state,
+/// written independently of [`crate::solver::search`] so the two can be compared.
+fn shortest_solution(start: &[Vec<u32>; PEG_COUNT], goal: Peg, rules: &Rules) -> Option<u64> {
+    let height = start.iter().map(|peg| peg.len()).sum::<usize>();
+    let thawed_after = rules.frozen.map_or(0, |frozen| frozen.moves);
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert((start.clone(), 0));
+    queue.push_back((start.clone(), 0));
+
+    while let Some((state, moves)) = queue.pop_front() {
+        if state[goal.index()].len() == height {
+            return Some(moves);
+        }
+        for from in Peg::all(PEG_COUNT) {
+            for to in Peg::all(PEG_COUNT) {
+                if !is_legal(&state, rules, moves, from, to) {
+                    continue;
+                }
+                let mut next = state.clone();
+                let disk = next[from.index()].pop().unwrap();
+                next[to.index()].push(disk);
+                // Past the freeze, states only differ by where the disks are.
+                if seen.insert((next.clone(), (moves + 1).min(thawed_after))) {
+                    queue.push_back((next, moves + 1));
+                }
+            }
+        }
+    }
+    None
+}
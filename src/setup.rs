@@ -0,0 +1,162 @@
+use std::io::{self, BufRead, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{Clear, ClearType::All},
+};
+
+use crate::{
+    exit_with_terminal_error, parse_capacity, parse_frozen, to_io_error, Clock, Config, Peg, Tower,
+    PEG_COUNT,
+};
+
+/// One question of the setup wizard. `apply` stores an answer in the config,
+/// or explains why it was not accepted.
+struct Screen {
+    title: &'static str,
+    prompt: &'static str,
+    current: fn(&Config) -> String,
+    apply: fn(&mut Config, &str) -> Result<(), String>,
+}
+
+const SCREENS: [Screen; 4] = [
+    Screen {
+        title: "Tower height",
+        prompt: "How many disks should the tower have?",
+        current: |config| config.height.to_string(),
+        apply: |config, answer| {
+            match answer.parse::<u32>() {
+                Ok(height) if height > 0 => config.height = height,
+                _ => return Err(format!("{} is not a positive integer", answer)),
+            }
+            Ok(())
+        },
+    },
+    Screen {
+        title: "Start peg",
+        prompt: "Which peg should the tower start on? (A, B or C)",
+        current: |config| config.start.to_string(),
+        apply: |config, answer| {
+            config.start = Peg::parse_within(answer, PEG_COUNT)?;
+            Ok(())
+        },
+    },
+    Screen {
+        title: "Goal peg",
+        prompt: "Which peg should the tower be moved to? (A, B or C)",
+        current: |config| config.goal.to_string(),
+        apply: |config, answer| {
+            config.goal = Peg::parse_within(answer, PEG_COUNT)?;
+            Ok(())
+        },
+    },
+    Screen {
+        title: "Variant",
+        prompt: "Play classic, limit a peg (e.g. B=2) or freeze a disk (e.g. 3:5)?",
+        current: |config| {
+            if let Some(frozen) = config.frozen {
+                format!("{}:{}", frozen.disk, frozen.moves)
+            } else {
+                Peg::all(PEG_COUNT)
+                    .find_map(|peg| {
+                        let capacity = config.capacities[peg.index()]?;
+                        Some(format!("{}={}", peg, capacity))
+                    })
+                    .unwrap_or_else(|| "classic".to_string())
+            }
+        },
+        apply: |config, answer| {
+            config.capacities = [None; PEG_COUNT];
+            config.frozen = None;
+            if answer == "classic" {
+                Ok(())
+            } else if let Some((peg, capacity)) = parse_capacity(answer) {
+                config.capacities[peg.index()] = Some(capacity);
+                Ok(())
+            } else if let Some(frozen) = parse_frozen(answer) {
+                config.frozen = Some(frozen);
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} is not classic, a peg limit or a freeze",
+                    answer
+                ))
+            }
+        },
+    },
+];
+
+/// Walks through every screen, previewing the starting position after each
+/// answer, until the whole configuration is valid and confirmed.
+pub fn run(config: &mut Config) {
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut screen_i = 0;
+    let mut message = String::new();
+    loop {
+        draw_preview(config, &message).unwrap_or_else(|e| exit_with_terminal_error(e));
+        message.clear();
+        let answer = match SCREENS.get(screen_i) {
+            Some(screen) => {
+                println!("[{}/{}] {}", screen_i + 1, SCREENS.len(), screen.title);
+                print!("{} [{}] ", screen.prompt, (screen.current)(config));
+                read_answer(&mut lines)
+            }
+            None => {
+                print!("Press Enter to start, or type a step number to change it: ");
+                read_answer(&mut lines)
+            }
+        };
+        match SCREENS.get(screen_i) {
+            Some(screen) => {
+                if !answer.is_empty() {
+                    if let Err(e) = (screen.apply)(config, &answer) {
+                        message = e;
+                        continue;
+                    }
+                }
+                screen_i += 1;
+            }
+            None if answer.is_empty() => match config.validate() {
+                Ok(()) => return,
+                Err(e) => message = e,
+            },
+            None => match answer.parse::<usize>() {
+                Ok(step) if step >= 1 && step <= SCREENS.len() => screen_i = step - 1,
+                _ => message = format!("{} is not a step number", answer),
+            },
+        }
+    }
+}
+
+fn read_answer(lines: &mut io::Lines<io::StdinLock>) -> String {
+    io::stdout()
+        .flush()
+        .unwrap_or_else(|e| exit_with_terminal_error(e));
+    match lines.next() {
+        Some(Ok(line)) => line.trim().to_string(),
+        _ => {
+            println!();
+            std::process::exit(0);
+        }
+    }
+}
+
+fn draw_preview(config: &Config, message: &str) -> io::Result<()> {
+    execute!(io::stdout(), Clear(All), MoveTo(0, 0)).map_err(to_io_error)?;
+    println!("Setup - starting position:\n");
+    let mut tower = Tower::new(
+        config.height,
+        config.delay,
+        config.start,
+        config.goal,
+        Clock::new(true),
+    );
+    tower.set_rules(config.rules());
+    println!("{}", tower);
+    if !message.is_empty() {
+        println!("{}!\n", message);
+    }
+    Ok(())
+}
@@ -0,0 +1,350 @@
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+use crate::{Peg, PEG_COUNT};
+
+/// The tallest tower [`search`] will attempt, as it visits up to 3^height states.
+pub const MAX_SEARCH_HEIGHT: u32 = 12;
+/// The most states [`search`] will track, counting each move of a freeze separately.
+pub const MAX_SEARCH_STATES: usize = 1 << 23;
+/// Marks a state [`search`] has not reached in its list of predecessors.
+const UNREACHED: u32 = u32::MAX;
+/// Marks the starting state, which has no predecessor.
+const STARTING: u32 = u32::MAX - 1;
+
+/// A disk that may not move until `moves` moves have been made.
+#[derive(Clone, Copy)]
+pub struct Frozen {
+    pub disk: u32,
+    pub moves: u64,
+}
+
+/// Restrictions on which moves are legal, beyond never placing a larger disk
+/// on a smaller one.
+#[derive(Clone, Copy)]
+pub struct Rules {
+    pub capacities: [Option<u32>; PEG_COUNT],
+    pub frozen: Option<Frozen>,
+}
+
+/// How many moves the classic solution of a `height` tall tower takes,
+/// `2^height - 1`, or `u64::MAX` for towers too tall to count them.
+pub fn optimal_moves(height: u32) -> u64 {
+    1u64.checked_shl(height).map_or(u64::MAX, |moves| moves - 1)
+}
+
+impl Rules {
+    pub fn classic() -> Self {
+        Rules {
+            capacities: [None; PEG_COUNT],
+            frozen: None,
+        }
+    }
+
+    /// Whether the recursive solution for a `height` tall tower never breaks
+    /// these rules, so no search is needed. It holds at most `height - 1`
+    /// disks on the auxiliary peg at any time, and first moves disk `d` on
+    /// move number `2^(d - 1)`.
+    pub fn allows_closed_form(&self, height: u32, start: Peg, goal: Peg) -> bool {
+        let within_capacity = Peg::all(PEG_COUNT).all(|peg| {
+            let needed = if peg == start || peg == goal {
+                height
+            } else {
+                height.saturating_sub(1)
+            };
+            self.capacities[peg.index()].is_none_or(|capacity| capacity >= needed)
+        });
+        let thawed_in_time = self
+            .frozen
+            .is_none_or(|frozen| optimal_moves(frozen.disk - 1) >= frozen.moves);
+        within_capacity && thawed_in_time
+    }
+
+    /// Whether `disk` is still frozen once `moves` moves have been made.
+    pub fn is_frozen(&self, disk: u32, moves: u64) -> bool {
+        self.frozen
+            .is_some_and(|frozen| frozen.disk == disk && moves < frozen.moves)
+    }
+
+    /// How many states [`search`] may visit for a `height` tall tower.
+    pub fn search_states(&self, height: u32) -> usize {
+        let layers = self.frozen.map_or(1, |frozen| {
+            usize::try_from(frozen.moves).map_or(usize::MAX, |moves| moves.saturating_add(1))
+        });
+        PEG_COUNT.pow(height).saturating_mul(layers)
+    }
+
+    pub fn allows(&self, state: &[Vec<u32>; PEG_COUNT], moves: u64, from: Peg, to: Peg) -> bool {
+        let disk = match state[from.index()].last() {
+            Some(disk) => *disk,
+            None => return false,
+        };
+        if from == to
+            || state[to.index()].last().is_some_and(|top| *top < disk)
+            || self.is_frozen(disk, moves)
+        {
+            return false;
+        }
+        self.capacities[to.index()]
+            .is_none_or(|capacity| (state[to.index()].len() as u32) < capacity)
+    }
+}
+
+/// A relabelling of the pegs, sending peg `i` to `self.0[i]`. Relabelling a
+/// puzzle and its solution together gives a solution to the relabelled puzzle,
+/// so one solution stands for a whole family of them.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Permutation([Peg; PEG_COUNT]);
+
+impl Permutation {
+    pub fn identity() -> Self {
+        Permutation([Peg::new(0), Peg::new(1), Peg::new(2)])
+    }
+
+    /// Swaps the outer pegs, as if looking at the board in a mirror.
+    pub fn mirror() -> Self {
+        Permutation([Peg::new(2), Peg::new(1), Peg::new(0)])
+    }
+
+    /// Moves every peg one place to the right, the last one wrapping around.
+    pub fn rotation() -> Self {
+        Permutation([Peg::new(1), Peg::new(2), Peg::new(0)])
+    }
+
+    /// This relabelling followed by `next`.
+    pub fn then(&self, next: &Permutation) -> Self {
+        let mut pegs = self.0;
+        for peg in pegs.iter_mut() {
+            *peg = next.apply(*peg);
+        }
+        Permutation(pegs)
+    }
+
+    pub fn apply(&self, peg: Peg) -> Peg {
+        self.0[peg.index()]
+    }
+
+    pub fn apply_moves(&self, moves: &[(Peg, Peg)]) -> Vec<(Peg, Peg)> {
+        moves
+            .iter()
+            .map(|(from, to)| (self.apply(*from), self.apply(*to)))
+            .collect()
+    }
+
+    /// Moves each capacity limit along with its peg; a freeze is unaffected.
+    pub fn apply_rules(&self, rules: &Rules) -> Rules {
+        let mut capacities = [None; PEG_COUNT];
+        for peg in Peg::all(PEG_COUNT) {
+            capacities[self.apply(peg).index()] = rules.capacities[peg.index()];
+        }
+        Rules {
+            capacities,
+            frozen: rules.frozen,
+        }
+    }
+}
+
+/// Finds a shortest sequence of moves taking `start` to every disk stacked on
+/// `goal`, using a breadth-first search over the whole state space.
+///
+/// While a disk is frozen, states are also told apart by how many moves have
+/// been made, so the search can wait out the freeze.
+pub fn search(start: &[Vec<u32>; PEG_COUNT], goal: Peg, rules: &Rules) -> Option<Vec<(Peg, Peg)>> {
+    let height = start.iter().map(|peg| peg.len() as u32).sum::<u32>();
+    let positions = PEG_COUNT.pow(height);
+    let last_layer = rules.frozen.map_or(0, |frozen| {
+        usize::try_from(frozen.moves).unwrap_or(usize::MAX)
+    });
+    let start_code = encode(start);
+    let goal_code = (positions - 1) / (PEG_COUNT - 1) * goal.index();
+
+    // Each reached state holds the index of the state before it, shifted
+    // past the low four bits that hold the move between them, from * 4 + to.
+    // That keeps the list at four bytes a state: MAX_SEARCH_STATES << 4 fits.
+    let mut previous = vec![UNREACHED; rules.search_states(height)];
+    let mut queue = VecDeque::new();
+    previous[start_code] = STARTING;
+    queue.push_back(start_code as u32);
+
+    while let Some(index) = queue.pop_front() {
+        let index = index as usize;
+        let (layer, code) = (index / positions, index % positions);
+        if code == goal_code {
+            let mut moves = Vec::new();
+            let mut index = index;
+            while previous[index] != STARTING {
+                let entry = previous[index];
+                let peg = |bits: u32| Peg::new((bits & 3) as usize);
+                moves.push((peg(entry >> 2), peg(entry)));
+                index = (entry >> 4) as usize;
+            }
+            moves.reverse();
+            return Some(moves);
+        }
+        let state = decode(code, height);
+        let next_layer = (layer + 1).min(last_layer);
+        for from in Peg::all(PEG_COUNT) {
+            for to in Peg::all(PEG_COUNT) {
+                if !rules.allows(&state, layer as u64, from, to) {
+                    continue;
+                }
+                let disk = *state[from.index()].last().unwrap();
+                let weight = PEG_COUNT.pow(disk - 1);
+                let next_code = code - from.index() * weight + to.index() * weight;
+                let next = next_layer * positions + next_code;
+                if previous[next] == UNREACHED {
+                    previous[next] =
+                        (index as u32) << 4 | (from.index() as u32) << 2 | to.index() as u32;
+                    queue.push_back(next as u32);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Packs a state into a number whose base-3 digits give each disk's peg,
+/// smallest disk first.
+fn encode(state: &[Vec<u32>; PEG_COUNT]) -> usize {
+    let mut code = 0;
+    for (peg, disks) in state.iter().enumerate() {
+        for disk in disks {
+            code += peg * PEG_COUNT.pow(disk - 1);
+        }
+    }
+    code
+}
+
+fn decode(mut code: usize, height: u32) -> [Vec<u32>; PEG_COUNT] {
+    let mut pegs = Vec::new();
+    for _ in 0..height {
+        pegs.push(code % PEG_COUNT);
+        code /= PEG_COUNT;
+    }
+    let mut state = [Vec::new(), Vec::new(), Vec::new()];
+    for disk in (1..=height).rev() {
+        state[pegs[disk as usize - 1]].push(disk);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tower(height: u32, peg: usize) -> [Vec<u32>; PEG_COUNT] {
+        let mut state = [Vec::new(), Vec::new(), Vec::new()];
+        state[peg] = (1..=height).rev().collect();
+        state
+    }
+
+    /// Plays `moves` from `start` under `rules`, panicking on an illegal move.
+    fn replay(
+        start: &[Vec<u32>; PEG_COUNT],
+        rules: &Rules,
+        moves: &[(Peg, Peg)],
+    ) -> [Vec<u32>; PEG_COUNT] {
+        let mut state = start.clone();
+        for (made, (from, to)) in moves.iter().enumerate() {
+            assert!(rules.allows(&state, made as u64, *from, *to));
+            let disk = state[from.index()].pop().unwrap();
+            state[to.index()].push(disk);
+        }
+        state
+    }
+
+    #[test]
+    fn optimal_moves_saturates() {
+        assert_eq!(optimal_moves(0), 0);
+        assert_eq!(optimal_moves(3), 7);
+        assert_eq!(optimal_moves(63), u64::MAX / 2);
+        assert_eq!(optimal_moves(64), u64::MAX);
+        assert_eq!(optimal_moves(65), u64::MAX);
+    }
+
+    #[test]
+    fn search_finds_the_classic_optimum() {
+        let rules = Rules::classic();
+        for height in 1..=6 {
+            let start = tower(height, 0);
+            let moves = search(&start, Peg::new(2), &rules).unwrap();
+            assert_eq!(moves.len() as u64, optimal_moves(height));
+            assert_eq!(replay(&start, &rules, &moves), tower(height, 2));
+        }
+    }
+
+    #[test]
+    fn search_detects_an_unsolvable_capacity() {
+        let rules = Rules {
+            capacities: [None, Some(0), None],
+            frozen: None,
+        };
+        assert!(search(&tower(2, 0), Peg::new(2), &rules).is_none());
+    }
+
+    #[test]
+    fn search_waits_out_a_freeze() {
+        let rules = Rules {
+            capacities: [None; PEG_COUNT],
+            frozen: Some(Frozen { disk: 2, moves: 3 }),
+        };
+        let start = tower(2, 0);
+        assert!(!rules.allows_closed_form(2, Peg::new(0), Peg::new(2)));
+        let moves = search(&start, Peg::new(2), &rules).unwrap();
+        assert_eq!(moves.len(), 5);
+        assert_eq!(replay(&start, &rules, &moves), tower(2, 2));
+    }
+
+    #[test]
+    fn closed_form_allows_a_freeze_that_ends_in_time() {
+        let freeze = |moves| Rules {
+            capacities: [None; PEG_COUNT],
+            frozen: Some(Frozen { disk: 2, moves }),
+        };
+        assert!(freeze(1).allows_closed_form(3, Peg::new(0), Peg::new(2)));
+        assert!(!freeze(2).allows_closed_form(3, Peg::new(0), Peg::new(2)));
+    }
+
+    #[test]
+    fn permutations_compose_in_order() {
+        let (mirror, rotation) = (Permutation::mirror(), Permutation::rotation());
+        let peg = Peg::new(0);
+        assert_eq!(
+            mirror.then(&rotation).apply(peg),
+            rotation.apply(mirror.apply(peg))
+        );
+        assert!(mirror.then(&mirror) == Permutation::identity());
+        let mut turned = Permutation::identity();
+        for _ in 0..PEG_COUNT {
+            turned = turned.then(&rotation);
+        }
+        assert!(turned == Permutation::identity());
+        assert!(mirror.then(&rotation) != rotation.then(&mirror));
+    }
+
+    #[test]
+    fn relabelled_solutions_solve_the_relabelled_puzzle() {
+        let rules = Rules {
+            capacities: [None, Some(2), None],
+            frozen: None,
+        };
+        let start = tower(3, 0);
+        let moves = search(&start, Peg::new(2), &rules).unwrap();
+        let permutation = Permutation::rotation();
+        let relabelled_rules = permutation.apply_rules(&rules);
+        assert_eq!(relabelled_rules.capacities, [None, None, Some(2)]);
+        let relabelled = replay(
+            &tower(3, 1),
+            &relabelled_rules,
+            &permutation.apply_moves(&moves),
+        );
+        assert_eq!(relabelled, tower(3, 0));
+    }
+
+    #[test]
+    fn decode_undoes_encode() {
+        for code in 0..PEG_COUNT.pow(4) {
+            assert_eq!(encode(&decode(code, 4)), code);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::{fmt, str::FromStr};
+
+/// A value the status bar can show, written `{name}` in a template.
+#[derive(Clone, Copy)]
+pub enum StatusField {
+    Action,
+    Move,
+    Total,
+    Percent,
+    Elapsed,
+    Speed,
+    Fps,
+}
+
+impl StatusField {
+    const ALL: [StatusField; 7] = [
+        StatusField::Action,
+        StatusField::Move,
+        StatusField::Total,
+        StatusField::Percent,
+        StatusField::Elapsed,
+        StatusField::Speed,
+        StatusField::Fps,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusField::Action => "action",
+            StatusField::Move => "move",
+            StatusField::Total => "total",
+            StatusField::Percent => "percent",
+            StatusField::Elapsed => "elapsed",
+            StatusField::Speed => "speed",
+            StatusField::Fps => "fps",
+        }
+    }
+
+    /// Every field name, for listing in the help.
+    pub fn names() -> Vec<&'static str> {
+        StatusField::ALL.iter().map(StatusField::name).collect()
+    }
+}
+
+#[derive(Clone)]
+enum Segment {
+    Text(String),
+    Field(StatusField),
+}
+
+/// A status bar layout such as `{move}/{total} {elapsed}`: text is kept as
+/// is, `{field}` is replaced by its value and `{{` or `}}` writes a brace.
+#[derive(Clone)]
+pub struct StatusTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl StatusTemplate {
+    /// The layout used by --status: what the solver is currently doing.
+    pub fn action() -> Self {
+        "{action}".parse().unwrap()
+    }
+
+    pub fn render(&self, value: impl Fn(StatusField) -> String) -> String {
+        let mut status_string = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => status_string.push_str(text),
+                Segment::Field(field) => status_string.push_str(&value(*field)),
+            }
+        }
+        status_string
+    }
+}
+
+impl fmt::Display for StatusTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl FromStr for StatusTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("{{{} is not closed", name)),
+                        }
+                    }
+                    let field = StatusField::ALL
+                        .iter()
+                        .find(|field| field.name() == name)
+                        .ok_or_else(|| format!("{{{}}} is not a status field", name))?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(*field));
+                }
+                '}' => return Err("a } has no matching {".to_string()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(StatusTemplate {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str) -> String {
+        let template: StatusTemplate = template.parse().unwrap();
+        template.render(|field| field.name().to_uppercase())
+    }
+
+    #[test]
+    fn displays_as_written() {
+        for source in ["{action}", "{move}/{total} {{{percent}}}", "plain", ""].iter() {
+            let template: StatusTemplate = source.parse().unwrap();
+            assert_eq!(template.to_string(), *source);
+        }
+    }
+
+    #[test]
+    fn replaces_fields_and_escaped_braces() {
+        assert_eq!(render("{move}/{total}"), "MOVE/TOTAL");
+        assert_eq!(render("{{{fps}}} fps"), "{FPS} fps");
+        assert_eq!(StatusTemplate::action().render(|_| "x".to_string()), "x");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        for invalid in ["{speed", "{colour}", "}", "a } b"].iter() {
+            assert!(
+                invalid.parse::<StatusTemplate>().is_err(),
+                "{} parsed",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    event::KeyCode,
+    execute,
+    terminal::{Clear, ClearType::All},
+};
+
+use crate::{
+    exit_with_terminal_error, is_quit_key, read_key,
+    solver::{Permutation, Rules},
+    to_io_error, Clock, Config, Peg, SolveError, Tower, PEG_COUNT,
+};
+
+/// How many moves of the relabelled solution are listed.
+const SHOWN_MOVES: usize = 15;
+
+/// Solves the configured tower once, then lets the user mirror and rotate the
+/// pegs, showing the relabelled solution and whether solving the relabelled
+/// puzzle directly gives the same moves.
+pub fn run(config: &Config) {
+    let rules = config.rules();
+    let solution = match solve(config.height, config.start, config.goal, &rules) {
+        Ok(solution) => solution,
+        Err(e) => {
+            println!("Could not solve the tower: {}!", e);
+            std::process::exit(0);
+        }
+    };
+    let mut permutation = Permutation::identity();
+    loop {
+        draw(config, &rules, &solution, &permutation)
+            .unwrap_or_else(|e| exit_with_terminal_error(e));
+        print!("[m]irror, [r]otate, [i]dentity or [q]uit");
+        let key = io::stdout()
+            .flush()
+            .and_then(|()| read_key())
+            .unwrap_or_else(|e| exit_with_terminal_error(e));
+        if is_quit_key(&key) {
+            println!();
+            return;
+        }
+        permutation = match key.code {
+            KeyCode::Char('m') | KeyCode::Char('M') => permutation.then(&Permutation::mirror()),
+            KeyCode::Char('r') | KeyCode::Char('R') => permutation.then(&Permutation::rotation()),
+            KeyCode::Char('i') | KeyCode::Char('I') => Permutation::identity(),
+            _ => permutation,
+        };
+    }
+}
+
+/// The moves the solver makes for a `height` tall tower under `rules`.
+fn solve(height: u32, start: Peg, goal: Peg, rules: &Rules) -> Result<Vec<(Peg, Peg)>, String> {
+    let mut tower = Tower::new(height, 0, start, goal, Clock::new(true));
+    tower.set_rules(*rules);
+    tower.set_headless();
+    tower.record_history();
+    match tower.solve() {
+        Ok(()) => Ok(tower.history.unwrap_or_default()),
+        Err(SolveError::Unsolvable(e)) => Err(e),
+        Err(SolveError::Output(e)) => Err(e.to_string()),
+        Err(SolveError::Cancelled) => Err("the solve was cancelled".to_string()),
+    }
+}
+
+fn draw(
+    config: &Config,
+    rules: &Rules,
+    solution: &[(Peg, Peg)],
+    permutation: &Permutation,
+) -> io::Result<()> {
+    let start = permutation.apply(config.start);
+    let goal = permutation.apply(config.goal);
+    let rules = permutation.apply_rules(rules);
+    let moves = permutation.apply_moves(solution);
+
+    execute!(io::stdout(), Clear(All), MoveTo(0, 0)).map_err(to_io_error)?;
+    let relabelling: Vec<String> = Peg::all(PEG_COUNT)
+        .map(|peg| format!("{}→{}", peg, permutation.apply(peg)))
+        .collect();
+    println!(
+        "Symmetry explorer - pegs relabelled {}\n",
+        relabelling.join(", ")
+    );
+    let mut tower = Tower::new(config.height, 0, start, goal, Clock::new(true));
+    tower.set_rules(rules);
+    println!("{}", tower);
+
+    println!(
+        "Solution from {} to {}, relabelled from {} to {}:",
+        start, goal, config.start, config.goal
+    );
+    let shown: Vec<String> = moves
+        .iter()
+        .take(SHOWN_MOVES)
+        .map(|(from, to)| format!("{}→{}", from, to))
+        .collect();
+    print!("  {}", shown.join(" "));
+    match moves.len().saturating_sub(SHOWN_MOVES) {
+        0 => println!(),
+        more => println!(" and {} more", config.locale.format_number(more as u64)),
+    }
+    match solve(config.height, start, goal, &rules) {
+        Ok(direct) if direct == moves => {
+            println!(
+                "Solving from {} to {} directly gives the same moves.",
+                start, goal
+            )
+        }
+        Ok(direct) if direct.len() == moves.len() => println!(
+            "Solving from {} to {} directly gives a different solution of the same length.",
+            start, goal
+        ),
+        Ok(direct) => println!(
+            "Solving from {} to {} directly takes {} moves instead!",
+            start,
+            goal,
+            config.locale.format_number(direct.len() as u64)
+        ),
+        Err(e) => println!("Solving from {} to {} directly fails: {}!", start, goal, e),
+    }
+    println!("The 3 pegs have 6 relabellings; mirroring and rotating reach all of them.\n");
+    Ok(())
+}